@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use phase_finance::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use phase_finance::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         query: QueryMsg,
         execute: ExecuteMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -2,38 +2,48 @@
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    to_binary, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdError, StdResult, SubMsgResponse, Uint128,
+    coin, from_binary, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Reply,
+    Response, StdError, StdResult, Storage, Uint128,
 };
 
-use cw2::set_contract_version;
-use cw_denom::validate_native_denom;
-use cw_utils::must_pay;
+use cw2::{get_contract_version, set_contract_version};
+use cw_utils::parse_reply_execute_data;
+use semver::Version;
 
-use crate::execute::{pause_dca, resume_dca, try_cancel_dca, try_perform_dca};
-use crate::helpers::token_string_to_coin;
+use crate::execute::{
+    pause_dca, resume_dca, try_cancel_dca, try_deposit, try_perform_dca, try_retry_failed,
+    try_settle_dca, try_update_config, try_withdraw,
+};
+use crate::helpers::{
+    add_coins, build_dca_config, estimate_croncat_funding, get_create_task_msg, get_due_trades,
+    get_first_swap, get_later_swap, get_next_swap, get_platform_fee_msg, get_unspent_funds,
+    parse_swap_reply_id, save_swap_history, swap_history_key, update_average_prices,
+};
+use crate::migrations::migrate_from_v0_1_0;
 use crate::query::{
     query_all_upcoming_swaps, query_bonded_funds, query_config, query_funds, query_state,
-    query_upcoming_swap,
+    query_swap_history, query_upcoming_swap,
+};
+use crate::state::{
+    CONFIG, CRONCAT_TASK_HASH, FACTORY, PENDING_SWAPS, PENDING_TIP, REPLACED_TASK_BALANCE,
+    RETRY_QUEUE, STATE, SWAP_HISTORY, TOTAL_SPENT,
 };
-use crate::state::{CONFIG, STATE};
 
-use phase_finance::constants::DCA_SWAP_ID;
+use phase_finance::constants::{
+    CRONCAT_CREATE_TASK_ID, CRONCAT_REFILL_TASK_ID, CRONCAT_REMOVE_TASK_ID,
+    CRONCAT_REPLACE_TASK_ID, DCA_RETRY_REPLY_FLAG, DCA_SWAP_REPLY_FLAG,
+};
 use phase_finance::error::ContractError;
-use phase_finance::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use phase_finance::types::{DcaConfig, State, SwapEvent};
+use phase_finance::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use phase_finance::types::{
+    CatchUpPolicy, DcaConfig, DcaStatus, FailurePolicy, State, StrategyType, SwapEvent,
+};
+use swaprouter::msg::SwapResponse;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:phase-finance";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-// the maximum number of destination tokens to dca into
-const MAX_DESTINATIONS: u8 = 25;
-// the maximum value for max_slippage on swaps
-const MAX_SLIPPAGE_PERCENTAGE: u128 = 15;
-// the maximum twap window in seconds for swaps
-const MAX_TWAP_WINDOW_SECONDS: u64 = 120;
-
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -43,110 +53,35 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let funds = must_pay(&info, &msg.source_denom)?;
-
-    let expected_funds = msg
-        .amount_per_trade
-        .checked_mul(msg.num_trades)
-        .expect("overflow")
-        .checked_add(msg.platform_fee)
-        .expect("overflow");
-
-    // check that amount deposited is correct for dca params
-    if expected_funds.ne(&funds) {
-        return Err(ContractError::CustomError {
-            val: format!(
-                "Amount deposited does not match exactly expected: <{expected_funds}> != actual: <{funds}>"
-            ),
-        });
-    }
-
-    // check that number of destination tokens is no more than MAX_DESTINATIONS
-    if msg.destinations.len() > MAX_DESTINATIONS.into() || msg.destinations.is_empty() {
-        return Err(ContractError::CustomError {
-            val: format!("Number of destination tokens must be between 1 and {MAX_DESTINATIONS}"),
-        });
-    }
-
-    // validate max_slippage is less than MAX_SLIPPAGE_PERCENTAGE%
-    if msg
-        .max_slippage
-        .gt(&Decimal::from_ratio(MAX_SLIPPAGE_PERCENTAGE, 100u128))
-    {
-        return Err(ContractError::CustomError {
-            val: format!("Max slippage must be between 0% and {MAX_SLIPPAGE_PERCENTAGE}%"),
-        });
-    }
-
-    // validate that twap_window_seconds is between 1 and MAX_TWAP_WINDOW_SECONDS seconds
-    if msg.twap_window_seconds.gt(&MAX_TWAP_WINDOW_SECONDS) || msg.twap_window_seconds.lt(&1u64) {
-        return Err(ContractError::CustomError {
-            val: format!("Twap window must be between 1 and {MAX_TWAP_WINDOW_SECONDS} seconds"),
-        });
-    }
-
-    // check that swap_interval is greater than 0
-    let swap_interval_value = match msg.swap_interval {
-        cw_utils::Duration::Height(height) => height,
-        cw_utils::Duration::Time(time) => time,
-    };
-    if swap_interval_value == 0 {
-        return Err(ContractError::CustomError {
-            val: "Swap interval must be greater than 0".to_string(),
-        });
-    }
-
-    let executor_address = match msg.executor_address {
-        Some(executor_address) => deps.api.addr_validate(&executor_address)?,
-        None => info.sender.clone(),
-    };
-
-    for destination in msg.destinations.iter() {
-        validate_native_denom(destination.denom.clone())?;
-    }
-
-    // store config for this DCA
-    let config = DcaConfig {
-        owner: info.sender.to_string(),
-        executor_address,
-        recipient_address: deps.api.addr_validate(&msg.recipient_address)?.to_string(),
-        strategy_type: msg.strategy_type,
-        source_denom: msg.source_denom.clone(),
-        destinations: msg.destinations,
-        max_slippage: msg.max_slippage,
-        twap_window_seconds: msg.twap_window_seconds,
-        amount_per_trade: msg.amount_per_trade,
-        num_trades: msg.num_trades,
-        swap_interval: msg.swap_interval,
-        router_contract: deps.api.addr_validate(&msg.router_contract)?,
-    };
+    let config = build_dca_config(deps.as_ref(), &info, &msg)?;
 
     let state = State {
-        next_swap: msg.swap_interval.after(&env.block),
+        status: DcaStatus::Active,
+        next_swap: get_first_swap(&env.block, &config)?,
         paused: false,
         num_trades_executed: Uint128::zero(),
         swap_status: vec![],
+        total_bought: vec![],
+        carry_over: vec![],
+        average_prices: vec![],
     };
 
     CONFIG.save(deps.storage, &config)?;
     STATE.save(deps.storage, &state)?;
-
-    let mut msgs = vec![];
-    if !msg.platform_fee.is_zero() {
-        msgs.push(BankMsg::Send {
-            to_address: deps
-                .api
-                .addr_validate(&msg.platform_fee_recipient)?
-                .to_string(),
-            amount: vec![Coin {
-                amount: msg.platform_fee,
-                denom: msg.source_denom,
-            }],
-        });
+    if let Some(factory) = &msg.factory {
+        FACTORY.save(deps.storage, &deps.api.addr_validate(factory)?)?;
     }
 
+    let msgs = get_platform_fee_msg(deps.as_ref(), &msg)?;
+    let task_msg = get_create_task_msg(
+        &env,
+        &config,
+        estimate_croncat_funding(&config, config.num_trades),
+    )?;
+
     Ok(Response::new()
         .add_messages(msgs)
+        .add_submessages(task_msg)
         .add_attribute("method", "instantiate")
         .add_attribute("creator", info.sender))
 }
@@ -163,144 +98,427 @@ pub fn execute(
         ExecuteMsg::PauseDca {} => pause_dca(deps, info),
         ExecuteMsg::ResumeDca {} => resume_dca(deps, env, info),
         ExecuteMsg::CancelDca {} => try_cancel_dca(deps, env, info),
+        ExecuteMsg::SettleDca {} => try_settle_dca(deps, env),
+        ExecuteMsg::UpdateConfig(msg) => try_update_config(deps, env, info, msg),
+        ExecuteMsg::Deposit { extra_trades } => try_deposit(deps, env, info, extra_trades),
+        ExecuteMsg::Withdraw { trades } => try_withdraw(deps, env, info, trades),
+        ExecuteMsg::RetryFailed {} => try_retry_failed(deps, env, info),
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = get_contract_version(deps.storage)?;
+    let previous_version = parse_version(&previous.version)?;
+
+    // only upgrades of this contract are allowed
+    if previous.contract != CONTRACT_NAME || previous_version > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: previous.contract,
+            previous_version: previous.version,
+        });
+    }
+
+    // every layout change adds a step here, they run in order from the stored version
+    if previous_version < parse_version("0.2.0")? {
+        migrate_from_v0_1_0(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", previous.version)
+        .add_attribute("new_version", CONTRACT_VERSION))
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    Version::parse(version).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+/// every swap reply id carries the trade and destination the swap was sent for
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
     match msg.id {
-        DCA_SWAP_ID => match msg.result {
-            cosmwasm_std::SubMsgResult::Ok(reply_msg) => {
-                // in the function below, we will need to get the swapEvent timestamp to avoid the edge case
-                // where a swap is executed just before the next swap begins, and we receive the swap response after,
-                // setting the swapEvent.executed value to true on the next swap event rather than the one we want.
-                // But I am tired and I forgot how to pass the swapEvent key correctly.
-                process_dca_swap_response(deps, env, reply_msg)
-            }
-            cosmwasm_std::SubMsgResult::Err(_) => {
-                try_store_and_finish_dca_swap(deps, env, Option::None)
-            }
-        },
-        _ => Err(StdError::GenericErr {
+        CRONCAT_CREATE_TASK_ID => return process_create_task_response(deps, msg),
+        CRONCAT_REMOVE_TASK_ID => return process_remove_task_response(deps, env),
+        CRONCAT_REFILL_TASK_ID => return process_refill_task_response(deps, msg),
+        CRONCAT_REPLACE_TASK_ID => return process_replace_task_response(deps, env),
+        _ => {}
+    }
+
+    if let Some(reply_key) = parse_swap_reply_id(DCA_RETRY_REPLY_FLAG, msg.id) {
+        return process_retry_swap_response(deps, reply_key, msg);
+    }
+
+    let reply_key =
+        parse_swap_reply_id(DCA_SWAP_REPLY_FLAG, msg.id).ok_or_else(|| StdError::GenericErr {
             msg: "unknown reply id".to_string(),
-        }),
+        })?;
+
+    match msg.result {
+        cosmwasm_std::SubMsgResult::Ok(_) => process_dca_swap_response(deps, env, reply_key, msg),
+        cosmwasm_std::SubMsgResult::Err(_) => {
+            try_store_and_finish_dca_swap(deps, env, reply_key, Option::None)
+        }
     }
 }
 
 pub fn try_store_and_finish_dca_swap(
     deps: DepsMut,
     env: Env,
-    swap_event: Option<SwapEvent>,
+    reply_key: (u64, u8),
+    token_out: Option<Coin>,
 ) -> StdResult<Response> {
     let mut state = STATE.load(deps.storage)?;
     let config = CONFIG.load(deps.storage)?;
     let mut swap_status = state.swap_status;
 
-    swap_status.push(swap_event.unwrap_or(SwapEvent {
-        executed: false,
-        token_in: Option::None,
-        effective_token_out: Option::None,
+    // a reply can only be credited to the trade that is being executed
+    let (trade_index, _) = swap_history_key(state.num_trades_executed, 0)?;
+    if reply_key.0 != trade_index {
+        return Err(StdError::generic_err(format!(
+            "Swap reply for trade {} arrived during trade {trade_index}",
+            reply_key.0
+        )));
+    }
+
+    // every swap waits for exactly one reply
+    let token_in = PENDING_SWAPS
+        .may_load(deps.storage, reply_key)?
+        .ok_or_else(|| StdError::generic_err("unexpected swap reply"))?;
+    PENDING_SWAPS.remove(deps.storage, reply_key);
+
+    // a swap without output failed
+    let swap_event = SwapEvent {
+        executed: token_out.is_some(),
+        token_in: Some(token_in),
+        effective_token_out: token_out,
         timestamp_nanos: env.block.time.nanos(),
-    }));
+    };
+    save_swap_history(deps.storage, reply_key, &swap_event)?;
+
+    let mut msgs = vec![];
+    let mut refunded = Uint128::zero();
+    if !swap_event.executed {
+        let token_in = swap_event.token_in.clone().unwrap();
+        match config.failure_policy {
+            // value averaging buys whatever was missed with its next trade anyway
+            FailurePolicy::RollOver => {
+                if !matches!(config.strategy_type, StrategyType::ValueAveraging { .. }) {
+                    let i = usize::from(reply_key.1);
+                    if state.carry_over.len() < config.destinations.len() {
+                        state
+                            .carry_over
+                            .resize(config.destinations.len(), Uint128::zero());
+                    }
+                    state.carry_over[i] += token_in.amount;
+                }
+            }
+            FailurePolicy::RetryQueue => RETRY_QUEUE.save(deps.storage, reply_key, &token_in)?,
+            FailurePolicy::Refund => {
+                refunded = token_in.amount;
+                msgs.push(BankMsg::Send {
+                    to_address: config.owner.clone(),
+                    amount: vec![token_in],
+                })
+            }
+        }
+    }
+
+    swap_status.push(swap_event);
     state.swap_status = swap_status;
 
     // if we have gotten back all the responses we were expecting, then we can finish the swap
     if state.swap_status.len() == config.destinations.len() {
-        // now that we have attempted all swaps, we can send the destination coins to the destination wallet
-        let msg = BankMsg::Send {
-            to_address: config.recipient_address.to_string(),
-            amount: state
-                .swap_status
-                .iter()
-                .filter(|swap_event| swap_event.executed)
-                .map(|swap_event| swap_event.effective_token_out.clone().unwrap())
-                .collect(),
-        };
-
-        // reset the state
-        // prepare for the next swap
-        state = State {
-            next_swap: config.swap_interval.after(&env.block),
-            paused: state.paused,
-            num_trades_executed: state
-                .num_trades_executed
-                .checked_add(Uint128::from(1u128))
-                .unwrap(),
-            swap_status: vec![],
-        };
-
-        // at this point, we have attempted all swaps, so save the state for the next swap
-        STATE.save(deps.storage, &state)?;
-
-        // respond with compiled swap events
-        // todo: add all swap events to the response
-        Ok(Response::new()
-            .add_message(msg)
-            .add_attribute("method", "try_store_and_finish_dca_swap"))
+        Ok(finish_dca_swap(deps, env, &config, state, refunded)?.add_messages(msgs))
     } else {
         // we are still waiting for more responses
         STATE.save(deps.storage, &state)?;
-        Ok(Response::new())
+        Ok(Response::new().add_messages(msgs))
     }
 }
 
-pub fn process_dca_swap_response(
+/// sends the bought coins to the recipient and prepares the state for the next swap,
+/// `outgoing` are source funds already sent away by the caller
+pub fn finish_dca_swap(
     deps: DepsMut,
     env: Env,
-    reply_msg: SubMsgResponse,
+    config: &DcaConfig,
+    state: State,
+    outgoing: Uint128,
 ) -> StdResult<Response> {
-    // find the event with ty = token_swapped
+    // the trade covered as many trades as were due when it was performed
+    let due_trades = get_due_trades(config, &state, &env.block)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
 
-    let event = reply_msg
-        .events
+    // now that we have attempted all swaps, we can send the destination coins to the destination wallet
+    let bought: Vec<Coin> = state
+        .swap_status
         .iter()
-        .find(|event| event.ty == "token_swapped");
-    if event.is_none() {
-        return try_store_and_finish_dca_swap(deps, env, Option::None);
+        .filter(|swap_event| swap_event.executed)
+        .map(|swap_event| swap_event.effective_token_out.clone().unwrap())
+        .collect();
+
+    let mut total_bought = state.total_bought;
+    add_coins(&mut total_bought, &bought);
+
+    // the executor is only tipped for a trade that bought something, otherwise the tip stays unspent
+    let mut outgoing = outgoing;
+    let mut tip = Uint128::zero();
+    let mut msgs = vec![];
+    if let Some((executor, pending_tip)) = PENDING_TIP.may_load(deps.storage)? {
+        PENDING_TIP.remove(deps.storage);
+        if !bought.is_empty() {
+            tip = pending_tip;
+            outgoing += tip;
+            msgs.push(BankMsg::Send {
+                to_address: executor.to_string(),
+                amount: vec![coin(tip.u128(), &config.source_denom)],
+            });
+        }
     }
 
-    // find the attribute with key = tokens_in
-    let tokens_in_attr = event
-        .unwrap()
-        .attributes
+    let spent = state
+        .swap_status
         .iter()
-        .find(|attr| attr.key == "tokens_in");
-    if tokens_in_attr.is_none() {
-        return try_store_and_finish_dca_swap(deps, env, Option::None);
+        .filter(|swap_event| swap_event.executed)
+        .filter_map(|swap_event| swap_event.token_in.as_ref())
+        .fold(Uint128::zero(), |acc, token_in| acc + token_in.amount);
+    add_total_spent(deps.storage, spent)?;
+
+    let average_prices = match &config.strategy_type {
+        StrategyType::BuyTheDip { smoothing, .. } => update_average_prices(
+            config,
+            &state.average_prices,
+            &state.swap_status,
+            *smoothing,
+        ),
+        _ => state.average_prices,
+    };
+
+    // the bank module rejects empty sends, which happens when every swap failed
+    if !bought.is_empty() {
+        msgs.push(BankMsg::Send {
+            to_address: config.recipient_address.to_string(),
+            amount: bought,
+        });
     }
-    let token_in = tokens_in_attr.unwrap().value.to_string();
 
-    // find the attribute with key = tokens_out
-    let tokens_out_attr = event
-        .unwrap()
-        .attributes
-        .iter()
-        .find(|attr| attr.key == "tokens_out");
-    if tokens_out_attr.is_none() {
-        return try_store_and_finish_dca_swap(deps, env, None);
+    let num_trades_executed = state
+        .num_trades_executed
+        .checked_add(Uint128::from(due_trades))
+        .unwrap();
+    let status = if num_trades_executed >= config.num_trades {
+        DcaStatus::Completed
+    } else {
+        DcaStatus::Active
+    };
+
+    // a completed strategy returns whatever it didn't spend, carried over amounts included
+    let mut carry_over = state.carry_over;
+    let mut swept = Uint128::zero();
+    if status == DcaStatus::Completed {
+        swept = get_unspent_funds(deps.as_ref(), &env, config, outgoing)?;
+        carry_over = vec![];
+        if !swept.is_zero() {
+            msgs.push(BankMsg::Send {
+                to_address: config.owner.clone(),
+                amount: vec![coin(swept.u128(), &config.source_denom)],
+            });
+        }
+    }
+
+    // reset the state
+    // prepare for the next swap, missed trades slide forward unless the cadence is kept
+    let next_swap = match config.catch_up_policy {
+        CatchUpPolicy::Skip => get_next_swap(&config.schedule, &env.block),
+        CatchUpPolicy::Batch { .. } | CatchUpPolicy::Anchor => {
+            get_later_swap(&config.schedule, state.next_swap, due_trades)
+        }
+    }
+    .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let state = State {
+        status,
+        next_swap,
+        paused: state.paused,
+        num_trades_executed,
+        swap_status: vec![],
+        total_bought,
+        carry_over,
+        average_prices,
+    };
+
+    // at this point, we have attempted all swaps, so save the state for the next swap
+    STATE.save(deps.storage, &state)?;
+
+    // respond with compiled swap events
+    // todo: add all swap events to the response
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "try_store_and_finish_dca_swap")
+        .add_attribute("executor_tip", tip)
+        .add_attribute("swept", swept))
+}
+
+fn add_total_spent(storage: &mut dyn Storage, spent: Uint128) -> StdResult<()> {
+    let total_spent = TOTAL_SPENT.may_load(storage)?.unwrap_or_default();
+    TOTAL_SPENT.save(storage, &total_spent.checked_add(spent)?)
+}
+
+/// the router answers every swap with a SwapResponse in the data of its execute response
+fn parse_swap_response(msg: Reply) -> StdResult<Coin> {
+    let data = parse_reply_execute_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?
+        .data
+        .ok_or_else(|| StdError::generic_err("missing swap response data"))?;
+    let res: SwapResponse = from_binary(&data)?;
+
+    Ok(coin(res.amount.u128(), res.token_out_denom))
+}
+
+pub fn process_dca_swap_response(
+    deps: DepsMut,
+    env: Env,
+    reply_key: (u64, u8),
+    msg: Reply,
+) -> StdResult<Response> {
+    let token_out = parse_swap_response(msg)?;
+    try_store_and_finish_dca_swap(deps, env, reply_key, Some(token_out))
+}
+
+/// a retried swap that succeeded leaves the queue and completes its history entry,
+/// a failed one stays queued for the next retry
+pub fn process_retry_swap_response(
+    deps: DepsMut,
+    reply_key: (u64, u8),
+    msg: Reply,
+) -> StdResult<Response> {
+    if msg.result.is_err() {
+        return Ok(Response::new().add_attribute("method", "process_retry_swap_response"));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    let token_out = parse_swap_response(msg)?;
+
+    let token_in = RETRY_QUEUE
+        .may_load(deps.storage, reply_key)?
+        .ok_or_else(|| StdError::generic_err("unexpected retry reply"))?;
+    RETRY_QUEUE.remove(deps.storage, reply_key);
+
+    SWAP_HISTORY.update(deps.storage, reply_key, |swap_event| {
+        let mut swap_event =
+            swap_event.ok_or_else(|| StdError::generic_err("missing swap history entry"))?;
+        swap_event.executed = true;
+        swap_event.effective_token_out = Some(token_out.clone());
+        StdResult::Ok(swap_event)
+    })?;
+
+    add_coins(&mut state.total_bought, std::slice::from_ref(&token_out));
+    STATE.save(deps.storage, &state)?;
+    add_total_spent(deps.storage, token_in.amount)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: config.recipient_address,
+            amount: vec![token_out],
+        })
+        .add_attribute("method", "process_retry_swap_response"))
+}
+
+/// the croncat manager answers with the hash of the task it created
+pub fn process_create_task_response(deps: DepsMut, msg: Reply) -> StdResult<Response> {
+    let data = parse_reply_execute_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?
+        .data
+        .ok_or_else(|| StdError::generic_err("missing task hash"))?;
+    let task_hash =
+        String::from_utf8(data.to_vec()).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    CRONCAT_TASK_HASH.save(deps.storage, &task_hash)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "process_create_task_response")
+        .add_attribute("task_hash", task_hash))
+}
+
+/// the task is removed after everything else was settled, so the source funds left
+/// are exactly the task balance the manager refunded. a task croncat already removed refunds nothing
+pub fn process_remove_task_response(deps: DepsMut, env: Env) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    CRONCAT_TASK_HASH.remove(deps.storage);
+
+    let refunded = deps
+        .querier
+        .query_balance(env.contract.address, &config.source_denom)?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "process_remove_task_response")
+        .add_attribute("task_refund", refunded.amount);
+    if !refunded.amount.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: config.owner,
+            amount: vec![refunded],
+        });
+    }
+
+    Ok(response)
+}
+
+/// the refund of the task on the old schedule funds the task on the new one, a task croncat
+/// already removed refunds nothing and the strategy is left without a task
+pub fn process_replace_task_response(deps: DepsMut, env: Env) -> StdResult<Response> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance_before = REPLACED_TASK_BALANCE.load(deps.storage)?;
+    REPLACED_TASK_BALANCE.remove(deps.storage);
+
+    let refunded = deps
+        .querier
+        .query_balance(env.contract.address.clone(), &config.source_denom)?
+        .amount
+        .saturating_sub(balance_before);
+
+    let mut response = Response::new()
+        .add_attribute("method", "process_replace_task_response")
+        .add_attribute("task_refund", refunded);
+    if !refunded.is_zero() {
+        response = response.add_submessages(
+            get_create_task_msg(&env, &config, refunded)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+        );
+    }
+
+    Ok(response)
+}
+
+/// a failed refill means croncat removed the task, its funding stayed with the strategy
+pub fn process_refill_task_response(deps: DepsMut, msg: Reply) -> StdResult<Response> {
+    let response = Response::new().add_attribute("method", "process_refill_task_response");
+    match msg.result {
+        cosmwasm_std::SubMsgResult::Ok(_) => Ok(response),
+        cosmwasm_std::SubMsgResult::Err(err) => {
+            CRONCAT_TASK_HASH.remove(deps.storage);
+            Ok(response.add_attribute("refill_error", err))
+        }
     }
-    let token_out = tokens_out_attr.unwrap().value.to_string();
-
-    try_store_and_finish_dca_swap(
-        deps,
-        env.clone(),
-        Some(SwapEvent {
-            executed: true,
-            token_in: token_string_to_coin(&token_in),
-            effective_token_out: token_string_to_coin(&token_out),
-            timestamp_nanos: env.block.time.nanos(),
-        }),
-    )
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetUpcomingSwap {} => to_binary(&query_upcoming_swap(deps, env)?),
-        QueryMsg::GetAllUpcomingSwaps {} => to_binary(&query_all_upcoming_swaps(deps, env)?),
-        QueryMsg::GetSourceFunds {} => to_binary(&query_bonded_funds(deps, env)?),
+        QueryMsg::GetAllUpcomingSwaps { start_after, limit } => {
+            to_binary(&query_all_upcoming_swaps(deps, env, start_after, limit)?)
+        }
+        QueryMsg::GetSourceFunds => to_binary(&query_bonded_funds(deps, env)?),
         QueryMsg::GetAllFunds {} => to_binary(&query_funds(deps, env)?),
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::SwapHistory {
+            start_after,
+            limit,
+            destination,
+        } => to_binary(&query_swap_history(deps, start_after, limit, destination)?),
     }
 }
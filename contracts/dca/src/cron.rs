@@ -0,0 +1,224 @@
+//! next fire times of the cron expressions strategies can be scheduled with, so the contract
+//! knows when a trade is due without relying on croncat
+
+use std::str::FromStr;
+
+use phase_finance::error::ContractError;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+// every day and month combination shows up within 8 years, even the 29th of February
+const MAX_SEARCH_DAYS: u64 = 366 * 8;
+// days in each month of a leap year
+const MAX_DAYS_IN_MONTH: [u64; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_OF_WEEK_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// a cron expression with seconds, "sec min hour day-of-month month day-of-week", evaluated in UTC.
+/// fields take `*`, values, ranges `a-b`, steps `*/n` or `a-b/n` and lists of those, months and
+/// days of the week also take their names, sunday is 0 or 7
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    // one bit per allowed value
+    seconds: u64,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // like in crontab, a day matches either day field when both are restricted
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = ContractError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(invalid_cron(expression, "it must have 6 fields"));
+        }
+
+        let mut days_of_week = parse_field(fields[5], 0, 7, &DAY_OF_WEEK_NAMES)?;
+        // 7 is sunday as well
+        if days_of_week & 1 << 7 != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        let schedule = CronSchedule {
+            seconds: parse_field(fields[0], 0, 59, &[])?,
+            minutes: parse_field(fields[1], 0, 59, &[])?,
+            hours: parse_field(fields[2], 0, 23, &[])?,
+            days_of_month: parse_field(fields[3], 1, 31, &[])?,
+            months: parse_field(fields[4], 1, 12, &MONTH_NAMES)?,
+            days_of_week,
+            any_day_of_month: fields[3] == "*",
+            any_day_of_week: fields[5] == "*",
+        };
+
+        // e.g. "0 0 0 30 2 *" is valid syntax but never fires
+        let day_exists = (1..=12u64)
+            .filter(|month| has(schedule.months, *month))
+            .any(|month| {
+                (1..=MAX_DAYS_IN_MONTH[month as usize - 1])
+                    .any(|day| has(schedule.days_of_month, day))
+            });
+        if !day_exists && schedule.any_day_of_week {
+            return Err(invalid_cron(expression, "it never fires"));
+        }
+
+        Ok(schedule)
+    }
+}
+
+impl CronSchedule {
+    /// the first second after `after` (in seconds since the unix epoch) the schedule fires at
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        let start = after + 1;
+        let first_day = start / SECONDS_PER_DAY;
+
+        (first_day..first_day + MAX_SEARCH_DAYS)
+            .filter(|day| self.fires_on(*day))
+            .find_map(|day| {
+                let from = if day == first_day {
+                    start % SECONDS_PER_DAY
+                } else {
+                    0
+                };
+                self.first_second_of_day(from)
+                    .map(|second| day * SECONDS_PER_DAY + second)
+            })
+    }
+
+    fn fires_on(&self, day: u64) -> bool {
+        let (month, day_of_month) = civil_from_days(day);
+        // the unix epoch was a thursday
+        let day_of_week = (day + 4) % 7;
+
+        let matches_day_of_month = has(self.days_of_month, day_of_month);
+        let matches_day_of_week = has(self.days_of_week, day_of_week);
+        let matches_day = match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => matches_day_of_week,
+            (false, true) => matches_day_of_month,
+            (false, false) => matches_day_of_month || matches_day_of_week,
+        };
+
+        has(self.months, month) && matches_day
+    }
+
+    /// the first second of the day at or after `from` the schedule fires at
+    fn first_second_of_day(&self, from: u64) -> Option<u64> {
+        let (from_hour, from_minute, from_second) = (from / 3_600, from / 60 % 60, from % 60);
+
+        for hour in (from_hour..24).filter(|hour| has(self.hours, *hour)) {
+            let first_minute = if hour == from_hour { from_minute } else { 0 };
+            for minute in (first_minute..60).filter(|minute| has(self.minutes, *minute)) {
+                let first_second = if hour == from_hour && minute == from_minute {
+                    from_second
+                } else {
+                    0
+                };
+                if let Some(second) = (first_second..60).find(|second| has(self.seconds, *second)) {
+                    return Some(hour * 3_600 + minute * 60 + second);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn has(bits: u64, value: u64) -> bool {
+    bits & 1 << value != 0
+}
+
+fn invalid_cron(expression: &str, reason: &str) -> ContractError {
+    ContractError::CustomError {
+        val: format!("Invalid cron \"{expression}\": {reason}"),
+    }
+}
+
+fn parse_field(field: &str, min: u64, max: u64, names: &[&str]) -> Result<u64, ContractError> {
+    let parse_value = |value: &str| -> Result<u64, ContractError> {
+        let parsed = match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(value))
+        {
+            // names start at the first value of the field
+            Some(position) => position as u64 + min,
+            None => value
+                .parse::<u64>()
+                .map_err(|_| invalid_cron(field, &format!("{value} is not a number")))?,
+        };
+        if parsed < min || parsed > max {
+            return Err(invalid_cron(
+                field,
+                &format!("{parsed} is not between {min} and {max}"),
+            ));
+        }
+        Ok(parsed)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step = step
+                    .parse::<u64>()
+                    .map_err(|_| invalid_cron(field, &format!("{step} is not a number")))?;
+                if step == 0 {
+                    return Err(invalid_cron(field, "steps must be greater than 0"));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => (parse_value(first)?, parse_value(last)?),
+                // "a/n" runs from a to the end of the field
+                None if part.contains('/') => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if first > last {
+            return Err(invalid_cron(
+                field,
+                &format!("range {first}-{last} is reversed"),
+            ));
+        }
+
+        for value in (first..=last).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+/// month (1-12) and day of the month (1-31) of a number of days since the unix epoch,
+/// see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64) {
+    let days = days + 719_468;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+
+    (month, day)
+}
@@ -1,40 +1,136 @@
 use cosmwasm_std::{
-    ensure, ensure_eq, ensure_ne, to_binary, BankMsg, Coin, DepsMut, Env, MessageInfo, Response,
-    SubMsg, Uint128, WasmMsg,
+    ensure, ensure_eq, ensure_ne, to_binary, BankMsg, Coin, DepsMut, Env, MessageInfo, Order,
+    Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
 
-use phase_finance::constants::DCA_SWAP_ID;
+use phase_finance::constants::{
+    CRONCAT_REFILL_TASK_ID, CRONCAT_REMOVE_TASK_ID, CRONCAT_REPLACE_TASK_ID, DCA_RETRY_REPLY_FLAG,
+    DCA_SWAP_REPLY_FLAG,
+};
+use phase_finance::croncat::ExecuteMsg as CroncatExecuteMsg;
 use phase_finance::error::ContractError;
+use phase_finance::factory::ExecuteMsg as FactoryExecuteMsg;
+use phase_finance::msg::UpdateConfigMsg;
+use phase_finance::types::{DcaConfig, DcaStatus, ExecutionMode, State, StrategyType, SwapEvent};
 
-use crate::helpers::get_expiration_time;
-use crate::state::{CONFIG, STATE};
+use crate::contract::finish_dca_swap;
+use crate::helpers::{
+    check_deposit, coins_to_string, ensure_active, ensure_not_ended, estimate_croncat_funding,
+    get_buy_the_dip_funds, get_destination_funds, get_due_trades, get_executor_tip,
+    get_scheduled_swap, get_total_deposit, get_trade_amount, get_unspent_funds,
+    get_value_averaging_funds, is_above_max_price, is_executor, save_swap_event, swap_history_key,
+    swap_reply_id, validate_dca_config, validate_executor_policy, MAX_DESTINATIONS,
+};
+use crate::state::{
+    CONFIG, CRONCAT_TASK_HASH, FACTORY, PENDING_SWAPS, PENDING_TIP, REPLACED_TASK_BALANCE,
+    RETRY_QUEUE, STATE, TOTAL_SPENT,
+};
 
+/// ends the strategy for good: unspent funds go back to the owner, bought tokens to the recipient
 pub fn try_cancel_dca(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    ensure_not_settled(&state)?;
     ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
 
-    let balances = deps.querier.query_all_balances(env.contract.address)?;
-    if balances.is_empty() {
-        return Err(ContractError::NoBalance {});
+    let response = settle_funds(deps.branch(), &env, config, "try_cancel_dca")?;
+
+    state.status = DcaStatus::Cancelled;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(response)
+}
+
+/// settles a strategy past its end like a cancel, but anyone can trigger it
+pub fn try_settle_dca(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    ensure_not_settled(&state)?;
+    ensure!(
+        config
+            .end_at
+            .is_some_and(|end_at| end_at.is_expired(&env.block)),
+        ContractError::DcaNotEnded
+    );
+
+    let response = settle_funds(deps.branch(), &env, config, "try_settle_dca")?;
+
+    state.status = DcaStatus::Settled;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(response)
+}
+
+fn ensure_not_settled(state: &State) -> Result<(), ContractError> {
+    match state.status {
+        DcaStatus::Cancelled => Err(ContractError::DcaCancelled),
+        DcaStatus::Settled => Err(ContractError::DcaSettled),
+        _ => Ok(()),
     }
+}
 
-    let msg = BankMsg::Send {
-        to_address: config.owner,
-        amount: balances,
-    };
+/// sends the source funds to the owner and what was bought to the recipient
+fn settle_funds(
+    deps: DepsMut,
+    env: &Env,
+    config: DcaConfig,
+    method: &str,
+) -> Result<Response, ContractError> {
+    let (bought, refunded): (Vec<Coin>, Vec<Coin>) = deps
+        .querier
+        .query_all_balances(&env.contract.address)?
+        .into_iter()
+        .partition(|coin| config.destinations.iter().any(|d| d.denom == coin.denom));
+
+    let mut msgs = vec![];
+    if !refunded.is_empty() {
+        msgs.push(BankMsg::Send {
+            to_address: config.owner,
+            amount: refunded.clone(),
+        });
+    }
+    if !bought.is_empty() {
+        msgs.push(BankMsg::Send {
+            to_address: config.recipient_address,
+            amount: bought.clone(),
+        });
+    }
+
+    // queued retries were refunded with the rest of the balance
+    RETRY_QUEUE.clear(deps.storage);
+
+    // the task goes last, its refund is forwarded once everything else has left the contract.
+    // croncat may have removed the task already, which must not keep the owner from their funds
+    let mut task_msgs = vec![];
+    if let (Some(croncat), Some(task_hash)) =
+        (&config.croncat, CRONCAT_TASK_HASH.may_load(deps.storage)?)
+    {
+        task_msgs.push(SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: croncat.manager_address.clone(),
+                msg: to_binary(&CroncatExecuteMsg::RemoveTask { task_hash })?,
+                funds: vec![],
+            },
+            CRONCAT_REMOVE_TASK_ID,
+        ));
+    }
 
     Ok(Response::new()
-        .add_message(msg)
-        .add_attribute("method", "try_cancel_dca"))
+        .add_messages(msgs)
+        .add_submessages(task_msgs)
+        .add_attribute("method", method)
+        .add_attribute("refunded", coins_to_string(&refunded))
+        .add_attribute("settled", coins_to_string(&bought)))
 }
 
 pub fn pause_dca(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
+    ensure_active(&state)?;
     ensure!(!state.paused, ContractError::DcaPaused);
     ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
 
@@ -49,12 +145,14 @@ pub fn pause_dca(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractE
 pub fn resume_dca(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
+    ensure_active(&state)?;
+    ensure_not_ended(&config, &env.block)?;
     ensure!(state.paused, ContractError::DcaNotPaused);
     ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
 
     let state = STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
         if state.next_swap.is_expired(&env.block) {
-            state.next_swap = config.swap_interval.after(&env.block);
+            state.next_swap = get_scheduled_swap(&env.block, &config)?;
         }
         state.paused = false;
         Ok(state)
@@ -62,10 +160,258 @@ pub fn resume_dca(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response
 
     Ok(Response::new()
         .add_attribute("method", "resume_dca")
-        .add_attribute(
-            "next_swap_time",
-            get_expiration_time(state.next_swap).to_string(),
-        ))
+        .add_attribute("next_swap", state.next_swap.to_string()))
+}
+
+pub fn try_update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_active(&STATE.load(deps.storage)?)?;
+    ensure_not_ended(&config, &env.block)?;
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    if let Some(max_slippage) = msg.max_slippage {
+        config.max_slippage = max_slippage;
+    }
+    if let Some(twap_window_seconds) = msg.twap_window_seconds {
+        config.twap_window_seconds = twap_window_seconds;
+    }
+    if let Some(executor_policy) = msg.executor_policy {
+        config.executor_policy = validate_executor_policy(deps.api, &executor_policy)?;
+    }
+    let mut msgs: Vec<SubMsg> = vec![];
+    if let Some(recipient_address) = msg.recipient_address {
+        config.recipient_address = deps.api.addr_validate(&recipient_address)?.to_string();
+        // the factory indexes its strategies by recipient
+        if let Some(factory) = FACTORY.may_load(deps.storage)? {
+            msgs.push(SubMsg::new(WasmMsg::Execute {
+                contract_addr: factory.to_string(),
+                msg: to_binary(&FactoryExecuteMsg::UpdateRecipient {
+                    recipient: config.recipient_address.clone(),
+                })?,
+                funds: vec![],
+            }));
+        }
+    }
+    if let Some(router_contract) = msg.router_contract {
+        config.router_contract = deps.api.addr_validate(&router_contract)?;
+    }
+    if let Some(destination_weights) = msg.destination_weights {
+        // only the split can change, the bought denoms stay the same
+        ensure_eq!(
+            destination_weights.len(),
+            config.destinations.len(),
+            ContractError::CustomError {
+                val: format!(
+                    "Expected {} destination weights, got {}",
+                    config.destinations.len(),
+                    destination_weights.len()
+                )
+            }
+        );
+        for (destination, weight) in config.destinations.iter_mut().zip(destination_weights) {
+            destination.weight = weight;
+        }
+    }
+    if let Some(schedule) = msg.schedule.clone() {
+        config.schedule = schedule;
+    }
+
+    validate_dca_config(&config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    // the next swap is scheduled with the new schedule from now on, or from the start if it's ahead
+    if msg.schedule.is_some() {
+        STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+            state.next_swap = get_scheduled_swap(&env.block, &config)?;
+            Ok(state)
+        })?;
+
+        // the croncat task runs on the old schedule, so it is replaced by one funded with its refund
+        if let (Some(croncat), Some(task_hash)) =
+            (&config.croncat, CRONCAT_TASK_HASH.may_load(deps.storage)?)
+        {
+            let balance = deps
+                .querier
+                .query_balance(&env.contract.address, &config.source_denom)?
+                .amount;
+            REPLACED_TASK_BALANCE.save(deps.storage, &balance)?;
+            CRONCAT_TASK_HASH.remove(deps.storage);
+            msgs.push(SubMsg::reply_always(
+                WasmMsg::Execute {
+                    contract_addr: croncat.manager_address.clone(),
+                    msg: to_binary(&CroncatExecuteMsg::RemoveTask { task_hash })?,
+                    funds: vec![],
+                },
+                CRONCAT_REPLACE_TASK_ID,
+            ));
+        }
+    }
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("method", "try_update_config"))
+}
+
+pub fn try_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    extra_trades: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_active(&STATE.load(deps.storage)?)?;
+    ensure_not_ended(&config, &env.block)?;
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    ensure!(
+        !extra_trades.is_zero(),
+        ContractError::CustomError {
+            val: "Extra trades must be greater than 0".to_string()
+        }
+    );
+    // a ramp is spread over its number of trades, extending it would resize the remaining trades
+    ensure!(
+        !matches!(config.strategy_type, StrategyType::LinearRamp { .. }),
+        ContractError::CustomError {
+            val: "Linear ramp strategies cannot be extended".to_string()
+        }
+    );
+
+    // the extra trades cost whatever the longer schedule costs more
+    let total_before = get_total_deposit(&config)?;
+    config.num_trades = config
+        .num_trades
+        .checked_add(extra_trades)
+        .map_err(StdError::from)?;
+    // the croncat task is funded for the extra trades as well
+    let task_funding = estimate_croncat_funding(&config, extra_trades);
+    let expected_funds = get_total_deposit(&config)? - total_before + task_funding;
+    check_deposit(&info, &config.source_denom, expected_funds)?;
+
+    CONFIG.save(deps.storage, &config)?;
+
+    // a task croncat already removed can't be refilled, its funding then stays with the strategy
+    let mut msgs = vec![];
+    if let (Some(croncat), Some(task_hash)) =
+        (&config.croncat, CRONCAT_TASK_HASH.may_load(deps.storage)?)
+    {
+        msgs.push(SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: croncat.manager_address.clone(),
+                msg: to_binary(&CroncatExecuteMsg::RefillTaskBalance { task_hash })?,
+                funds: vec![Coin {
+                    denom: config.source_denom.clone(),
+                    amount: task_funding,
+                }],
+            },
+            CRONCAT_REFILL_TASK_ID,
+        ));
+    }
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("method", "try_deposit")
+        .add_attribute("num_trades", config.num_trades))
+}
+
+pub fn try_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    trades: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    ensure_active(&state)?;
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    ensure!(
+        !trades.is_zero(),
+        ContractError::CustomError {
+            val: "Trades must be greater than 0".to_string()
+        }
+    );
+    ensure!(
+        !matches!(config.strategy_type, StrategyType::LinearRamp { .. }),
+        ContractError::CustomError {
+            val: "Linear ramp strategies cannot be shortened".to_string()
+        }
+    );
+
+    // a trade with swaps in flight already spent its funds
+    let started_trades = if state.swap_status.is_empty() {
+        state.num_trades_executed
+    } else {
+        state.num_trades_executed + Uint128::one()
+    };
+    let num_trades = config.num_trades.checked_sub(trades).unwrap_or_default();
+    ensure!(
+        config.num_trades >= trades && num_trades >= started_trades,
+        ContractError::CustomError {
+            val: format!(
+                "Cannot withdraw more than the {} trades left",
+                config.num_trades.saturating_sub(started_trades)
+            )
+        }
+    );
+
+    // the removed trades and their tips are worth the difference between both schedules
+    let total_before = get_total_deposit(&config)?;
+    config.num_trades = num_trades;
+    let total_after = get_total_deposit(&config)?;
+    let amount = total_before - total_after;
+
+    // the trades that stay, and anything carried over or queued for them, have to stay funded
+    let mut spent = config.clone();
+    spent.num_trades = started_trades;
+    let queued = RETRY_QUEUE
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, coin)| coin.amount))
+        .sum::<StdResult<Uint128>>()?;
+    let reserved = state.carry_over.iter().fold(
+        total_after - get_total_deposit(&spent)? + queued,
+        |acc, c| acc + c,
+    );
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.source_denom)?
+        .amount;
+    ensure!(
+        balance >= reserved + amount,
+        ContractError::CustomError {
+            val: format!("Not enough unspent funds to withdraw {amount}")
+        }
+    );
+
+    CONFIG.save(deps.storage, &config)?;
+
+    // nothing is left to execute once every remaining trade is withdrawn, so whatever is unspent goes along
+    let mut amount = amount;
+    if config.num_trades == state.num_trades_executed && state.swap_status.is_empty() {
+        amount += get_unspent_funds(deps.as_ref(), &env, &config, amount)?;
+        state.status = DcaStatus::Completed;
+        state.carry_over = vec![];
+        STATE.save(deps.storage, &state)?;
+    }
+
+    let msg = BankMsg::Send {
+        to_address: config.owner,
+        amount: vec![Coin {
+            denom: config.source_denom,
+            amount,
+        }],
+    };
+
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("method", "try_withdraw")
+        .add_attribute("amount", amount)
+        .add_attribute("num_trades", config.num_trades))
 }
 
 pub fn try_perform_dca(
@@ -76,9 +422,11 @@ pub fn try_perform_dca(
     let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
 
-    ensure_eq!(
-        config.executor_address,
-        info.sender,
+    ensure_active(&state)?;
+    ensure_not_ended(&config, &env.block)?;
+
+    ensure!(
+        is_executor(&config, &info.sender),
         ContractError::Unauthorized {}
     );
 
@@ -93,49 +441,209 @@ pub fn try_perform_dca(
     ensure!(
         state.next_swap.is_expired(&env.block),
         ContractError::DcaSwapNotAllowedYet {
-            next_swap_event_time: get_expiration_time(state.next_swap)
+            next_swap: state.next_swap
         }
     );
+    let due_trades = get_due_trades(&config, &state, &env.block)?;
+
+    let is_value_averaging = matches!(config.strategy_type, StrategyType::ValueAveraging { .. });
+    let destination_funds = match &config.strategy_type {
+        StrategyType::ValueAveraging {
+            target_increment_per_period,
+        } => get_value_averaging_funds(
+            deps.as_ref(),
+            &env,
+            &config,
+            &state,
+            *target_increment_per_period,
+        )?,
+        StrategyType::BuyTheDip {
+            multipliers,
+            max_total_spend,
+            ..
+        } => {
+            let total_spent = TOTAL_SPENT.may_load(deps.storage)?.unwrap_or_default();
+            let spend_limit = max_total_spend.map(|max| max.saturating_sub(total_spent));
+            get_buy_the_dip_funds(
+                deps.as_ref(),
+                &env,
+                &config,
+                &state,
+                multipliers,
+                spend_limit,
+            )?
+        }
+        _ => {
+            // missed trades batched with this one are swapped along with it
+            let mut trade_amount = Uint128::zero();
+            for i in 0..due_trades {
+                trade_amount +=
+                    get_trade_amount(&config, state.num_trades_executed + Uint128::from(i))?;
+            }
+            // amounts skipped earlier because of max_price are bought together with this trade
+            get_destination_funds(&config, trade_amount)
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut funds)| {
+                    funds.amount += state.carry_over.get(i).copied().unwrap_or_default();
+                    funds
+                })
+                .collect()
+        }
+    };
 
-    let total_weight = config
+    let above_max_price = config
         .destinations
         .iter()
-        .fold(Uint128::zero(), |acc, d| acc + d.weight);
+        .zip(&destination_funds)
+        .map(|(d, in_funds)| {
+            Ok(!in_funds.amount.is_zero() && is_above_max_price(deps.as_ref(), &config, d)?)
+        })
+        .collect::<Result<Vec<bool>, ContractError>>()?;
+    // an atomic trade is bought in full or not at all, so one destination that is too expensive
+    // rolls the whole trade over
+    let skip_trade =
+        config.execution_mode == ExecutionMode::Atomic && above_max_price.contains(&true);
 
-    let msgs: Vec<SubMsg> = config
+    let mut state = state;
+    let mut carry_over = vec![Uint128::zero(); config.destinations.len()];
+    let mut msgs: Vec<SubMsg> = vec![];
+    for (i, (d, in_funds)) in config
         .destinations
         .iter()
-        .map(|d| {
-            let in_funds = Coin {
-                denom: config.source_denom.clone(),
-                amount: d
-                    .weight
-                    .checked_mul(config.amount_per_trade)
-                    .unwrap_or_default()
-                    .checked_div(total_weight)
-                    .unwrap_or_default(),
-            };
+        .zip(destination_funds)
+        .enumerate()
+    {
+        let skipped = above_max_price[i] || skip_trade;
+        // value averaging doesn't need a carry, its target keeps growing until it is bought
+        if skipped && !is_value_averaging {
+            carry_over[i] = in_funds.amount;
+        }
 
-            let msg = WasmMsg::Execute {
-                contract_addr: config.router_contract.to_string(),
-                msg: to_binary(&swaprouter::msg::ExecuteMsg::Swap {
-                    input_coin: in_funds.clone(),
-                    output_denom: d.denom.clone(),
-                    slippage: swaprouter::msg::Slippage::Twap {
-                        slippage_percentage: config.max_slippage,
-                        window_seconds: Option::Some(config.twap_window_seconds),
-                    },
-                })
-                .unwrap(),
-                funds: vec![in_funds],
+        // nothing to buy for this destination or it is too expensive, record it as a swap that didn't happen
+        if in_funds.amount.is_zero() || skipped {
+            let swap_event = SwapEvent {
+                executed: false,
+                token_in: Some(in_funds),
+                effective_token_out: None,
+                timestamp_nanos: env.block.time.nanos(),
             };
+            save_swap_event(deps.storage, state.num_trades_executed, i, &swap_event)?;
+            state.swap_status.push(swap_event);
+            continue;
+        }
 
-            SubMsg::reply_always(msg, DCA_SWAP_ID)
-        })
-        .collect();
+        // the swap is pending until its reply arrives
+        PENDING_SWAPS.save(
+            deps.storage,
+            swap_history_key(state.num_trades_executed, i)?,
+            &in_funds,
+        )?;
+
+        let msg = WasmMsg::Execute {
+            contract_addr: config.router_contract.to_string(),
+            msg: to_binary(&swaprouter::msg::ExecuteMsg::Swap {
+                input_coin: in_funds.clone(),
+                output_denom: d.denom.clone(),
+                slippage: swaprouter::msg::Slippage::Twap {
+                    slippage_percentage: config.max_slippage,
+                    window_seconds: Option::Some(config.twap_window_seconds),
+                },
+            })?,
+            funds: vec![in_funds],
+        };
+
+        // in atomic mode a failed swap reverts the whole trade, so only successes are replied to
+        let reply_id = swap_reply_id(DCA_SWAP_REPLY_FLAG, state.num_trades_executed, i)?;
+        msgs.push(match config.execution_mode {
+            ExecutionMode::Atomic => SubMsg::reply_on_success(msg, reply_id),
+            ExecutionMode::BestEffort => SubMsg::reply_always(msg, reply_id),
+        });
+    }
+    state.carry_over = carry_over;
+
+    // every destination was skipped, so the trade is already complete and there is nothing to tip for
+    if msgs.is_empty() {
+        let finished = finish_dca_swap(deps, env, &config, state, Uint128::zero())?;
+        return Ok(Response::new()
+            .add_submessages(finished.messages)
+            .add_attributes(finished.attributes)
+            .add_attribute("method", "try_perform_dca"));
+    }
+
+    // the tip is paid once the trade is finished and one of its swaps succeeded, every trade of a
+    // batch is tipped. croncat is paid by its fee per trade, so its manager isn't tipped
+    let performed_by_croncat = config
+        .croncat
+        .as_ref()
+        .is_some_and(|croncat| croncat.manager_address == info.sender.as_str());
+    let tip = get_executor_tip(&config) * Uint128::from(due_trades);
+    if !tip.is_zero() && !performed_by_croncat {
+        PENDING_TIP.save(deps.storage, &(info.sender, tip))?;
+    }
+
+    STATE.save(deps.storage, &state)?;
 
     // add the messages to swap & send funds to user
     Ok(Response::new()
         .add_submessages(msgs)
         .add_attribute("method", "try_perform_dca"))
 }
+
+/// swaps the queued input of failed swaps again, at most MAX_DESTINATIONS of them per call
+pub fn try_retry_failed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let state = STATE.load(deps.storage)?;
+    ensure_not_settled(&state)?;
+    ensure_not_ended(&config, &env.block)?;
+    ensure!(
+        is_executor(&config, &info.sender),
+        ContractError::Unauthorized {}
+    );
+
+    let queued = RETRY_QUEUE
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(MAX_DESTINATIONS.into())
+        .collect::<StdResult<Vec<_>>>()?;
+    ensure!(
+        !queued.is_empty(),
+        ContractError::CustomError {
+            val: "No failed swaps to retry".to_string()
+        }
+    );
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    for ((trade_index, destination_index), in_funds) in queued {
+        let d = &config.destinations[usize::from(destination_index)];
+        let msg = WasmMsg::Execute {
+            contract_addr: config.router_contract.to_string(),
+            msg: to_binary(&swaprouter::msg::ExecuteMsg::Swap {
+                input_coin: in_funds.clone(),
+                output_denom: d.denom.clone(),
+                slippage: swaprouter::msg::Slippage::Twap {
+                    slippage_percentage: config.max_slippage,
+                    window_seconds: Option::Some(config.twap_window_seconds),
+                },
+            })?,
+            funds: vec![in_funds],
+        };
+
+        msgs.push(SubMsg::reply_always(
+            msg,
+            swap_reply_id(
+                DCA_RETRY_REPLY_FLAG,
+                trade_index.into(),
+                destination_index.into(),
+            )?,
+        ));
+    }
+
+    Ok(Response::new()
+        .add_attribute("retried", msgs.len().to_string())
+        .add_submessages(msgs)
+        .add_attribute("method", "try_retry_failed"))
+}
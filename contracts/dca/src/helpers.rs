@@ -1,35 +1,1101 @@
-use cosmwasm_std::{coin, Coin};
-use cw_utils::Expiration;
+use std::str::FromStr;
 
-pub fn get_expiration_time(exp: Expiration) -> u64 {
-    match exp {
-        Expiration::AtTime(time) => time.seconds(),
-        _ => u64::MAX,
+use cosmwasm_std::{
+    to_binary, Addr, Api, BankMsg, BlockInfo, Coin, Decimal, Deps, Empty, Env, Fraction,
+    MessageInfo, Order, StdError, StdResult, Storage, SubMsg, Timestamp, Uint128, Uint256, WasmMsg,
+};
+use cw_denom::validate_native_denom;
+use cw_utils::{must_pay, Duration, Expiration};
+
+use phase_finance::constants::{CRONCAT_CREATE_TASK_ID, DCA_RETRY_REPLY_FLAG, DCA_SWAP_REPLY_FLAG};
+use phase_finance::croncat::{
+    Action, Boundary, CroncatConfig, ExecuteMsg as CroncatExecuteMsg, Interval, TaskRequest,
+};
+use phase_finance::error::ContractError;
+use phase_finance::msg::{ExecuteMsg, InstantiateMsg};
+use phase_finance::types::{
+    CatchUpPolicy, CoinWeight, DcaConfig, DcaStatus, DipMultiplier, ExecutionMode, ExecutorPolicy,
+    ExecutorTip, FailurePolicy, Schedule, State, StrategyType, SwapEvent,
+};
+use swaprouter::msg::{GetTwapPriceResponse, QueryMsg as RouterQueryMsg};
+
+use crate::cron::CronSchedule;
+use crate::state::{RETRY_QUEUE, SWAP_HISTORY, SWAP_HISTORY_BY_DESTINATION};
+
+// the maximum number of destination tokens to dca into
+pub const MAX_DESTINATIONS: u8 = 25;
+// the maximum value for max_slippage on swaps
+pub const MAX_SLIPPAGE_PERCENTAGE: u128 = 15;
+// the maximum twap window in seconds for swaps
+pub const MAX_TWAP_WINDOW_SECONDS: u64 = 120;
+// basis points in a whole
+const BASIS_POINTS_DENOMINATOR: u128 = 10_000;
+// the highest trade index that fits in a swap reply id
+const DCA_SWAP_REPLY_TRADE_MAX: u64 = (1 << 54) - 1;
+// the maximum multiplier of a buy the dip trade
+pub const MAX_DIP_MULTIPLIER: u128 = 10;
+// the maximum executor tip in basis points of the amount per trade
+pub const MAX_EXECUTOR_TIP_BPS: u16 = 1_000;
+
+/// validates the dca params and the deposit sent along with them, and builds the strategy config
+pub fn build_dca_config(
+    deps: Deps,
+    info: &MessageInfo,
+    msg: &InstantiateMsg,
+) -> Result<DcaConfig, ContractError> {
+    let croncat = match &msg.croncat {
+        Some(croncat) => Some(CroncatConfig {
+            manager_address: deps
+                .api
+                .addr_validate(&croncat.manager_address)?
+                .to_string(),
+            ..croncat.clone()
+        }),
+        None => None,
+    };
+
+    let owner = match &msg.owner {
+        Some(owner) => deps.api.addr_validate(owner)?,
+        None => info.sender.clone(),
+    };
+
+    // a croncat task performs trades from its manager, otherwise the owner performs them, which
+    // is not the sender when the strategy is created through the factory
+    let executor_policy = match (&msg.executor_policy, &croncat) {
+        (Some(executor_policy), _) => validate_executor_policy(deps.api, executor_policy)?,
+        (None, Some(croncat)) => ExecutorPolicy::Single(Addr::unchecked(&croncat.manager_address)),
+        (None, None) => ExecutorPolicy::Single(owner.clone()),
+    };
+
+    let config = DcaConfig {
+        owner: owner.to_string(),
+        executor_policy,
+        recipient_address: deps.api.addr_validate(&msg.recipient_address)?.to_string(),
+        strategy_type: msg.strategy_type.clone(),
+        failure_policy: msg
+            .failure_policy
+            .clone()
+            .unwrap_or(FailurePolicy::RollOver),
+        execution_mode: msg
+            .execution_mode
+            .clone()
+            .unwrap_or(ExecutionMode::BestEffort),
+        catch_up_policy: msg.catch_up_policy.clone().unwrap_or(CatchUpPolicy::Skip),
+        executor_tip: msg.executor_tip.clone(),
+        source_denom: msg.source_denom.clone(),
+        destinations: msg.destinations.clone(),
+        max_slippage: msg.max_slippage,
+        twap_window_seconds: msg.twap_window_seconds,
+        amount_per_trade: msg.amount_per_trade,
+        num_trades: msg.num_trades,
+        schedule: msg.schedule.clone(),
+        start_at: msg.start_at,
+        end_at: msg.end_at,
+        router_contract: deps.api.addr_validate(&msg.router_contract)?,
+        croncat,
+    };
+
+    validate_dca_config(&config)?;
+
+    let expected_funds = get_total_deposit(&config)?
+        .checked_add(msg.platform_fee)
+        .and_then(|funds| funds.checked_add(estimate_croncat_funding(&config, config.num_trades)))
+        .expect("overflow");
+    check_deposit(info, &msg.source_denom, expected_funds)?;
+
+    Ok(config)
+}
+
+/// validates the addresses of an executor policy, an allowlist needs at least one of them
+pub fn validate_executor_policy(
+    api: &dyn Api,
+    executor_policy: &ExecutorPolicy,
+) -> Result<ExecutorPolicy, ContractError> {
+    Ok(match executor_policy {
+        ExecutorPolicy::Single(executor) => {
+            ExecutorPolicy::Single(api.addr_validate(executor.as_str())?)
+        }
+        ExecutorPolicy::Allowlist(executors) => {
+            if executors.is_empty() {
+                return Err(ContractError::CustomError {
+                    val: "Executor allowlist must not be empty".to_string(),
+                });
+            }
+            ExecutorPolicy::Allowlist(
+                executors
+                    .iter()
+                    .map(|executor| api.addr_validate(executor.as_str()))
+                    .collect::<StdResult<_>>()?,
+            )
+        }
+        ExecutorPolicy::Anyone => ExecutorPolicy::Anyone,
+    })
+}
+
+/// whether the sender may perform the trades of the strategy
+pub fn is_executor(config: &DcaConfig, sender: &Addr) -> bool {
+    match &config.executor_policy {
+        ExecutorPolicy::Single(executor) => executor == sender,
+        ExecutorPolicy::Allowlist(executors) => executors.contains(sender),
+        ExecutorPolicy::Anyone => true,
     }
 }
 
-pub fn token_string_to_coin(token_string: &str) -> Option<Coin> {
-    if token_string.is_empty() {
-        return None;
+/// checks that exactly the expected amount of source tokens was sent
+pub fn check_deposit(
+    info: &MessageInfo,
+    source_denom: &str,
+    expected_funds: Uint128,
+) -> Result<(), ContractError> {
+    let funds = must_pay(info, source_denom)?;
+
+    // check that amount deposited is correct for dca params
+    if expected_funds.ne(&funds) {
+        return Err(ContractError::CustomError {
+            val: format!(
+                "Amount deposited does not match exactly expected: <{expected_funds}> != actual: <{funds}>"
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+/// checks the strategy params, for a new strategy as well as after every config update
+pub fn validate_dca_config(config: &DcaConfig) -> Result<(), ContractError> {
+    // check that number of destination tokens is no more than MAX_DESTINATIONS
+    if config.destinations.len() > MAX_DESTINATIONS.into() || config.destinations.is_empty() {
+        return Err(ContractError::CustomError {
+            val: format!("Number of destination tokens must be between 1 and {MAX_DESTINATIONS}"),
+        });
+    }
+
+    // validate max_slippage is less than MAX_SLIPPAGE_PERCENTAGE%
+    if config
+        .max_slippage
+        .gt(&Decimal::from_ratio(MAX_SLIPPAGE_PERCENTAGE, 100u128))
+    {
+        return Err(ContractError::CustomError {
+            val: format!("Max slippage must be between 0% and {MAX_SLIPPAGE_PERCENTAGE}%"),
+        });
+    }
+
+    // validate that twap_window_seconds is between 1 and MAX_TWAP_WINDOW_SECONDS seconds
+    if config.twap_window_seconds.gt(&MAX_TWAP_WINDOW_SECONDS)
+        || config.twap_window_seconds.lt(&1u64)
+    {
+        return Err(ContractError::CustomError {
+            val: format!("Twap window must be between 1 and {MAX_TWAP_WINDOW_SECONDS} seconds"),
+        });
+    }
+
+    match &config.schedule {
+        // check that swap_interval is greater than 0
+        Schedule::Interval(Duration::Height(0) | Duration::Time(0)) => {
+            return Err(ContractError::CustomError {
+                val: "Swap interval must be greater than 0".to_string(),
+            });
+        }
+        Schedule::Interval(_) => {}
+        Schedule::Cron(cron) => {
+            CronSchedule::from_str(cron)?;
+        }
+    }
+
+    // a destination without weight never buys anything, nor does a trade without any weight
+    for destination in config.destinations.iter() {
+        if destination.weight.is_zero() {
+            return Err(ContractError::CustomError {
+                val: format!("Weight of {} must be greater than 0", destination.denom),
+            });
+        }
+    }
+
+    // start and end are checked again whenever the schedule changes
+    for expiration in config.start_at.iter().chain(config.end_at.iter()) {
+        let measured_like_schedule = matches!(
+            (&config.schedule, expiration),
+            (
+                Schedule::Interval(Duration::Height(_)),
+                Expiration::AtHeight(_)
+            ) | (
+                Schedule::Interval(Duration::Time(_)) | Schedule::Cron(_),
+                Expiration::AtTime(_)
+            )
+        );
+        if !measured_like_schedule {
+            return Err(ContractError::CustomError {
+                val: "Start and end must be heights or times like the schedule".to_string(),
+            });
+        }
+    }
+    if let (Some(start_at), Some(end_at)) = (config.start_at, config.end_at) {
+        if end_at <= start_at {
+            return Err(ContractError::CustomError {
+                val: "End must be after start".to_string(),
+            });
+        }
+    }
+
+    // every destination has to get a share of every trade, the smallest of a schedule is its first or last one
+    if !config.num_trades.is_zero() {
+        let smallest_trade = get_trade_amount(config, Uint128::zero())?.min(get_trade_amount(
+            config,
+            config.num_trades - Uint128::one(),
+        )?);
+        let total_weight = config
+            .destinations
+            .iter()
+            .fold(Uint128::zero(), |acc, d| acc + d.weight);
+        // trades of 0 tokens are rejected along with the deposit of the schedule
+        for destination in config.destinations.iter() {
+            if !smallest_trade.is_zero()
+                && destination.weight.full_mul(smallest_trade) < Uint256::from(total_weight)
+            {
+                return Err(ContractError::CustomError {
+                    val: format!(
+                        "Share of {} in a trade of {smallest_trade} rounds to 0",
+                        destination.denom
+                    ),
+                });
+            }
+        }
+    }
+
+    for destination in config.destinations.iter() {
+        validate_native_denom(destination.denom.clone())?;
+
+        if destination.max_price == Some(Decimal::zero()) {
+            return Err(ContractError::CustomError {
+                val: format!("Max price of {} must be greater than 0", destination.denom),
+            });
+        }
+    }
+
+    if let Some(croncat) = &config.croncat {
+        if croncat.fee_per_trade.is_zero() {
+            return Err(ContractError::CustomError {
+                val: "Croncat fee per trade must be greater than 0".to_string(),
+            });
+        }
+        if !is_executor(config, &Addr::unchecked(&croncat.manager_address)) {
+            return Err(ContractError::CustomError {
+                val: "Croncat manager must be allowed to perform trades".to_string(),
+            });
+        }
+        get_croncat_interval(&config.schedule)?;
+    }
+
+    match config.executor_tip {
+        Some(ExecutorTip::Bps { bps }) if bps > MAX_EXECUTOR_TIP_BPS => {
+            return Err(ContractError::CustomError {
+                val: format!("Executor tip must be at most {MAX_EXECUTOR_TIP_BPS} bps"),
+            });
+        }
+        // only linear trades swap the amount per trade, the others would be tipped an arbitrary share
+        Some(ExecutorTip::Bps { .. }) if !matches!(config.strategy_type, StrategyType::Linear) => {
+            return Err(ContractError::CustomError {
+                val: "Executor tips in bps are only supported by linear strategies".to_string(),
+            });
+        }
+        Some(_) if get_executor_tip(config).is_zero() => {
+            return Err(ContractError::CustomError {
+                val: "Executor tip must be greater than 0".to_string(),
+            });
+        }
+        _ => {}
+    }
+
+    if let CatchUpPolicy::Batch { max_trades } = config.catch_up_policy {
+        if max_trades == 0 {
+            return Err(ContractError::CustomError {
+                val: "Catch up batches must cover at least 1 trade".to_string(),
+            });
+        }
+        // their trades are sized by the prices of the moment, not by the schedule
+        if matches!(
+            config.strategy_type,
+            StrategyType::ValueAveraging { .. } | StrategyType::BuyTheDip { .. }
+        ) {
+            return Err(ContractError::CustomError {
+                val: "Missed trades of this strategy type can't be batched".to_string(),
+            });
+        }
+    }
+
+    if let StrategyType::ValueAveraging {
+        target_increment_per_period,
+    } = &config.strategy_type
+    {
+        if target_increment_per_period.is_zero() {
+            return Err(ContractError::CustomError {
+                val: "Target increment per period must be greater than 0".to_string(),
+            });
+        }
+    }
+
+    if let StrategyType::BuyTheDip {
+        multipliers,
+        smoothing,
+        max_total_spend,
+    } = &config.strategy_type
+    {
+        validate_dip_multipliers(multipliers, *smoothing)?;
+        if max_total_spend.is_some_and(|max_total_spend| max_total_spend.is_zero()) {
+            return Err(ContractError::CustomError {
+                val: "Max total spend must be greater than 0".to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// the amount of source tokens swapped by the trade with the given index (starting at 0)
+pub fn get_trade_amount(
+    config: &DcaConfig,
+    trade_index: Uint128,
+) -> Result<Uint128, ContractError> {
+    let amount = match &config.strategy_type {
+        StrategyType::Linear
+        | StrategyType::ValueAveraging { .. }
+        | StrategyType::BuyTheDip { .. } => config.amount_per_trade,
+        StrategyType::Exponential { factor } => {
+            let exponent =
+                u32::try_from(trade_index.u128()).map_err(|_| ContractError::CustomError {
+                    val: format!(
+                        "Trade index {trade_index} is too large for an exponential strategy"
+                    ),
+                })?;
+            let multiplier = factor.checked_pow(exponent).map_err(StdError::from)?;
+            config
+                .amount_per_trade
+                .checked_multiply_ratio(multiplier.numerator(), multiplier.denominator())
+                .map_err(|_| ContractError::CustomError {
+                    val: format!("Trade amount overflows for trade {trade_index}"),
+                })?
+        }
+        StrategyType::LinearRamp {
+            start_amount,
+            end_amount,
+        } => {
+            let last_index = config.num_trades.saturating_sub(Uint128::one());
+            if last_index.is_zero() {
+                *start_amount
+            } else if end_amount >= start_amount {
+                *start_amount
+                    + (*end_amount - *start_amount).multiply_ratio(trade_index, last_index)
+            } else {
+                *start_amount
+                    - (*start_amount - *end_amount).multiply_ratio(trade_index, last_index)
+            }
+        }
+    };
+
+    Ok(amount)
+}
+
+/// the sum of all trade amounts over the whole schedule, which is what has to be deposited
+pub fn get_total_trade_amount(config: &DcaConfig) -> Result<Uint128, ContractError> {
+    if let StrategyType::Linear
+    | StrategyType::ValueAveraging { .. }
+    | StrategyType::BuyTheDip { .. } = config.strategy_type
+    {
+        return Ok(config
+            .amount_per_trade
+            .checked_mul(config.num_trades)
+            .expect("overflow"));
+    }
+
+    let mut total = Uint128::zero();
+    let mut trade_index = Uint128::zero();
+    while trade_index < config.num_trades {
+        let amount = get_trade_amount(config, trade_index)?;
+        if amount.is_zero() {
+            return Err(ContractError::CustomError {
+                val: format!("Trade {trade_index} of the schedule would swap 0 tokens"),
+            });
+        }
+        total = total.checked_add(amount).map_err(StdError::from)?;
+        trade_index += Uint128::one();
+    }
+
+    Ok(total)
+}
+
+/// what the croncat task needs to execute the given number of trades, 0 without croncat
+pub fn estimate_croncat_funding(config: &DcaConfig, num_trades: Uint128) -> Uint128 {
+    config
+        .croncat
+        .as_ref()
+        .map(|croncat| croncat.fee_per_trade * num_trades)
+        .unwrap_or_default()
+}
+
+/// when the first trade after this block is due
+pub fn get_next_swap(schedule: &Schedule, block: &BlockInfo) -> Result<Expiration, ContractError> {
+    match schedule {
+        Schedule::Interval(swap_interval) => Ok(swap_interval.after(block)),
+        Schedule::Cron(cron) => get_cron_swap(cron, block.time.seconds(), 1),
+    }
+}
+
+/// when the first trade is due, start and end have to lie ahead
+pub fn get_first_swap(block: &BlockInfo, config: &DcaConfig) -> Result<Expiration, ContractError> {
+    for expiration in config.start_at.iter().chain(config.end_at.iter()) {
+        if expiration.is_expired(block) {
+            return Err(ContractError::CustomError {
+                val: "Start and end must be in the future".to_string(),
+            });
+        }
+    }
+
+    get_scheduled_swap(block, config)
+}
+
+/// when the next trade after this block is due, never before the start of the strategy
+pub fn get_scheduled_swap(
+    block: &BlockInfo,
+    config: &DcaConfig,
+) -> Result<Expiration, ContractError> {
+    match (&config.schedule, config.start_at) {
+        (_, Some(start_at)) if start_at.is_expired(block) => get_next_swap(&config.schedule, block),
+        (Schedule::Cron(cron), Some(Expiration::AtTime(start))) => {
+            // the schedule may fire at the start itself
+            let first_second = start.seconds() + u64::from(start.subsec_nanos() > 0);
+            get_cron_swap(cron, first_second.saturating_sub(1), 1)
+        }
+        (_, Some(start_at)) => Ok(start_at),
+        (schedule, None) => get_next_swap(schedule, block),
     }
+}
+
+/// how many trades the next call performs, more than one when missed trades are batched.
+/// replies arrive in the same block, so the trade they belong to gets the same answer
+pub fn get_due_trades(
+    config: &DcaConfig,
+    state: &State,
+    block: &BlockInfo,
+) -> Result<u64, ContractError> {
+    let max_trades = match config.catch_up_policy {
+        CatchUpPolicy::Batch { max_trades } => u64::from(max_trades),
+        CatchUpPolicy::Skip | CatchUpPolicy::Anchor => 1,
+    };
+    let trades_left = config
+        .num_trades
+        .saturating_sub(state.num_trades_executed)
+        .u128() as u64;
 
-    // lets scan token string until we find a character that isnt a number
-    let number_part = token_string
-        .chars()
-        .take_while(|c| c.is_numeric())
-        .collect::<String>();
+    let mut due_trades = 1;
+    let mut next_swap = state.next_swap;
+    while due_trades < max_trades.min(trades_left) {
+        next_swap = get_later_swap(&config.schedule, next_swap, 1)?;
+        if !next_swap.is_expired(block) {
+            break;
+        }
+        due_trades += 1;
+    }
 
-    // now lets grab the letters
-    let denom_part = token_string
-        .chars()
-        .skip(number_part.len())
-        .collect::<String>();
+    Ok(due_trades)
+}
+
+/// when the trade `trades_ahead` trades after the one due at `next_swap` is due
+pub fn get_later_swap(
+    schedule: &Schedule,
+    next_swap: Expiration,
+    trades_ahead: u64,
+) -> Result<Expiration, ContractError> {
+    match (schedule, next_swap) {
+        (Schedule::Interval(Duration::Height(height)), Expiration::AtHeight(next_height)) => height
+            .checked_mul(trades_ahead)
+            .and_then(|delta| next_height.checked_add(delta))
+            .map(Expiration::AtHeight)
+            .ok_or_else(swap_overflow),
+        (Schedule::Interval(Duration::Time(time)), Expiration::AtTime(next_time)) => time
+            .checked_mul(trades_ahead)
+            .and_then(|delta| delta.checked_mul(1_000_000_000))
+            .and_then(|delta| next_time.nanos().checked_add(delta))
+            .map(|nanos| Expiration::AtTime(Timestamp::from_nanos(nanos)))
+            .ok_or_else(swap_overflow),
+        (Schedule::Interval(swap_interval), _) => Ok((next_swap + *swap_interval)?),
+        (Schedule::Cron(cron), Expiration::AtTime(time)) => {
+            get_cron_swap(cron, time.seconds(), trades_ahead)
+        }
+        (Schedule::Cron(_), _) => Err(ContractError::CustomError {
+            val: "Trades of a cron schedule are due at a time".to_string(),
+        }),
+    }
+}
+
+/// the time a cron schedule fires at for the `count`th time after `after`
+fn get_cron_swap(cron: &str, after: u64, count: u64) -> Result<Expiration, ContractError> {
+    let schedule = CronSchedule::from_str(cron)?;
+    let mut time = after;
+    for _ in 0..count {
+        time = schedule
+            .next_after(time)
+            .ok_or_else(|| ContractError::CustomError {
+                val: format!("Cron schedule \"{cron}\" never fires"),
+            })?;
+    }
+
+    time.checked_mul(1_000_000_000)
+        .map(|nanos| Expiration::AtTime(Timestamp::from_nanos(nanos)))
+        .ok_or_else(swap_overflow)
+}
+
+fn swap_overflow() -> ContractError {
+    ContractError::CustomError {
+        val: "Trade is due too far ahead".to_string(),
+    }
+}
+
+/// the croncat interval of a schedule, cron schedules are passed as they are and interval times
+/// have to fit a cron step of seconds, minutes or hours
+pub fn get_croncat_interval(schedule: &Schedule) -> Result<Interval, ContractError> {
+    let cron = match schedule {
+        Schedule::Cron(cron) => Some(cron.clone()),
+        Schedule::Interval(Duration::Height(height)) => return Ok(Interval::Block(*height)),
+        Schedule::Interval(Duration::Time(86_400)) => Some("0 0 0 * * *".to_string()),
+        Schedule::Interval(Duration::Time(time)) if *time < 60 && 60 % time == 0 => {
+            Some(format!("*/{time} * * * * *"))
+        }
+        Schedule::Interval(Duration::Time(time))
+            if time % 60 == 0 && *time < 3_600 && 3_600 % time == 0 =>
+        {
+            Some(format!("0 */{} * * * *", time / 60))
+        }
+        Schedule::Interval(Duration::Time(time))
+            if time % 3_600 == 0 && *time < 86_400 && 86_400 % time == 0 =>
+        {
+            Some(format!("0 0 */{} * * *", time / 3_600))
+        }
+        Schedule::Interval(Duration::Time(_)) => None,
+    };
+
+    cron.map(Interval::Cron)
+        .ok_or_else(|| ContractError::CustomError {
+            val: "Swap interval can't be scheduled with croncat".to_string(),
+        })
+}
+
+/// the croncat boundary of a strategy window, start and end are measured like the schedule
+pub fn get_croncat_boundary(config: &DcaConfig) -> Option<Boundary> {
+    match (config.start_at, config.end_at) {
+        (None, None) => None,
+        (start, end) => Some(match &config.schedule {
+            Schedule::Interval(Duration::Height(_)) => Boundary::Height {
+                start: start.and_then(expiration_height),
+                end: end.and_then(expiration_height),
+            },
+            Schedule::Interval(Duration::Time(_)) | Schedule::Cron(_) => Boundary::Time {
+                start: start.and_then(expiration_nanos),
+                end: end.and_then(expiration_nanos),
+            },
+        }),
+    }
+}
+
+fn expiration_height(expiration: Expiration) -> Option<u64> {
+    match expiration {
+        Expiration::AtHeight(height) => Some(height),
+        _ => None,
+    }
+}
+
+fn expiration_nanos(expiration: Expiration) -> Option<u64> {
+    match expiration {
+        Expiration::AtTime(time) => Some(time.nanos()),
+        _ => None,
+    }
+}
 
-    let amount = number_part.trim().parse::<u128>();
+/// the task that performs the trades of the strategy within its window, funded with `funding`
+pub fn get_create_task_msg(
+    env: &Env,
+    config: &DcaConfig,
+    funding: Uint128,
+) -> Result<Option<SubMsg>, ContractError> {
+    let croncat = match &config.croncat {
+        Some(croncat) => croncat,
+        None => return Ok(None),
+    };
 
-    if amount.is_err() {
+    let task = TaskRequest {
+        interval: get_croncat_interval(&config.schedule)?,
+        boundary: get_croncat_boundary(config),
+        stop_on_fail: false,
+        actions: vec![Action {
+            msg: WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::PerformDca {})?,
+                funds: vec![],
+            }
+            .into(),
+            gas_limit: croncat.gas_limit,
+        }],
+    };
+
+    let msg = WasmMsg::Execute {
+        contract_addr: croncat.manager_address.clone(),
+        msg: to_binary(&CroncatExecuteMsg::CreateTask { task })?,
+        funds: vec![Coin {
+            denom: config.source_denom.clone(),
+            amount: funding,
+        }],
+    };
+
+    Ok(Some(SubMsg::reply_on_success(msg, CRONCAT_CREATE_TASK_ID)))
+}
+
+/// the tip paid for every trade, bps tips are a share of the amount per trade of a linear strategy
+pub fn get_executor_tip(config: &DcaConfig) -> Uint128 {
+    match config.executor_tip {
+        Some(ExecutorTip::Fixed { amount }) => amount,
+        Some(ExecutorTip::Bps { bps }) => config
+            .amount_per_trade
+            .multiply_ratio(bps, BASIS_POINTS_DENOMINATOR),
+        None => Uint128::zero(),
+    }
+}
+
+/// the tips of the trades that are still to be performed, they are never spent on swaps
+pub fn get_reserved_tips(config: &DcaConfig, state: &State) -> Uint128 {
+    get_executor_tip(config) * config.num_trades.saturating_sub(state.num_trades_executed)
+}
+
+/// what the whole schedule costs: every trade amount plus the tip of every trade
+pub fn get_total_deposit(config: &DcaConfig) -> Result<Uint128, ContractError> {
+    get_total_trade_amount(config)?
+        .checked_add(
+            get_executor_tip(config)
+                .checked_mul(config.num_trades)
+                .map_err(StdError::from)?,
+        )
+        .map_err(|err| StdError::from(err).into())
+}
+
+/// splits the amount of a trade between the destinations according to their weights,
+/// the units lost to rounding down go to the destinations with the largest remainders
+pub fn get_destination_funds(config: &DcaConfig, amount: Uint128) -> Vec<Coin> {
+    let total_weight = Uint256::from(
+        config
+            .destinations
+            .iter()
+            .fold(Uint128::zero(), |acc, d| acc + d.weight),
+    );
+
+    let mut shares: Vec<(Uint128, Uint128)> = config
+        .destinations
+        .iter()
+        .map(|d| {
+            let product = d.weight.full_mul(amount);
+            // both fit, a share is at most the amount and a remainder less than the total weight
+            (
+                Uint128::try_from(product.checked_div(total_weight).unwrap_or_default()).unwrap(),
+                Uint128::try_from(product.checked_rem(total_weight).unwrap_or_default()).unwrap(),
+            )
+        })
+        .collect();
+
+    let allocated = shares.iter().fold(Uint128::zero(), |acc, s| acc + s.0);
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    // ties go to the destination listed first
+    by_remainder.sort_by(|a, b| shares[*b].1.cmp(&shares[*a].1).then(a.cmp(b)));
+    // the leftover is less than the number of destinations, unless the weights are all 0
+    if !total_weight.is_zero() {
+        for i in by_remainder
+            .into_iter()
+            .take((amount - allocated).u128() as usize)
+        {
+            shares[i].0 += Uint128::one();
+        }
+    }
+
+    shares
+        .into_iter()
+        .map(|(amount, _)| Coin {
+            denom: config.source_denom.clone(),
+            amount,
+        })
+        .collect()
+}
+
+/// the source funds of a completed strategy that nothing will spend anymore,
+/// only the queued retries and what is already on its way out keep theirs
+pub fn get_unspent_funds(
+    deps: Deps,
+    env: &Env,
+    config: &DcaConfig,
+    outgoing: Uint128,
+) -> StdResult<Uint128> {
+    let queued = get_queued_funds(deps)?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.source_denom)?
+        .amount;
+
+    Ok(balance.saturating_sub(queued + outgoing))
+}
+
+/// the input of the failed swaps waiting in the retry queue, it is owed to their retries
+fn get_queued_funds(deps: Deps) -> StdResult<Uint128> {
+    RETRY_QUEUE
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, coin)| coin.amount))
+        .sum()
+}
+
+/// the source funds a trade may spend, everything deposited except the tips of the trades left
+/// and the input of the queued retries
+pub fn get_spendable_funds(
+    deps: Deps,
+    env: &Env,
+    config: &DcaConfig,
+    state: &State,
+) -> StdResult<Uint128> {
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.source_denom)?
+        .amount;
+
+    Ok(balance.saturating_sub(get_reserved_tips(config, state) + get_queued_funds(deps)?))
+}
+
+/// platform fee is paid out of the deposit as soon as the strategy is created
+pub fn get_platform_fee_msg(
+    deps: Deps,
+    msg: &InstantiateMsg,
+) -> Result<Vec<BankMsg>, ContractError> {
+    let mut msgs = vec![];
+    if !msg.platform_fee.is_zero() {
+        msgs.push(BankMsg::Send {
+            to_address: deps
+                .api
+                .addr_validate(&msg.platform_fee_recipient)?
+                .to_string(),
+            amount: vec![Coin {
+                amount: msg.platform_fee,
+                denom: msg.source_denom.clone(),
+            }],
+        });
+    }
+    Ok(msgs)
+}
+
+/// price of the output denom in the source denom as seen by the router, i.e. how many output
+/// tokens one source token buys
+pub fn query_twap_price(
+    deps: Deps,
+    config: &DcaConfig,
+    output_denom: &str,
+) -> Result<Decimal, ContractError> {
+    let res: GetTwapPriceResponse = deps.querier.query_wasm_smart(
+        config.router_contract.to_string(),
+        &RouterQueryMsg::GetTwapPrice {
+            input_denom: config.source_denom.clone(),
+            output_denom: output_denom.to_string(),
+            window_seconds: Some(config.twap_window_seconds),
+        },
+    )?;
+
+    if res.price.is_zero() {
+        return Err(ContractError::CustomError {
+            val: format!("Twap price for {output_denom} is 0"),
+        });
+    }
+
+    Ok(res.price)
+}
+
+/// whether the destination has a max_price and its twap price in source tokens is above it
+pub fn is_above_max_price(
+    deps: Deps,
+    config: &DcaConfig,
+    destination: &CoinWeight,
+) -> Result<bool, ContractError> {
+    let max_price = match destination.max_price {
+        Some(max_price) => max_price,
+        None => return Ok(false),
+    };
+
+    // the router quotes destination tokens per source token, the ceiling is the inverse
+    let price = query_twap_price(deps, config, &destination.denom)?
+        .inv()
+        .expect("twap price is never 0");
+
+    Ok(price > max_price)
+}
+
+/// the input of every destination for a value averaging trade: whatever is missing for the value of
+/// the tokens bought so far to reach the target of this period, bounded by the spendable funds
+pub fn get_value_averaging_funds(
+    deps: Deps,
+    env: &Env,
+    config: &DcaConfig,
+    state: &State,
+    target_increment_per_period: Uint128,
+) -> Result<Vec<Coin>, ContractError> {
+    let period = state.num_trades_executed + Uint128::one();
+    let target = target_increment_per_period
+        .checked_mul(period)
+        .map_err(StdError::from)?;
+
+    let mut budget = get_spendable_funds(deps, env, config, state)?;
+
+    let mut funds = vec![];
+    for (destination, target_funds) in config
+        .destinations
+        .iter()
+        .zip(get_destination_funds(config, target))
+    {
+        let price = query_twap_price(deps, config, &destination.denom)?;
+        let bought = state
+            .total_bought
+            .iter()
+            .find(|coin| coin.denom == destination.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+        let value = bought.multiply_ratio(price.denominator(), price.numerator());
+
+        let amount = target_funds.amount.saturating_sub(value).min(budget);
+        budget -= amount;
+
+        funds.push(Coin {
+            denom: config.source_denom.clone(),
+            amount,
+        });
+    }
+
+    Ok(funds)
+}
+
+fn validate_dip_multipliers(
+    multipliers: &[DipMultiplier],
+    smoothing: Decimal,
+) -> Result<(), ContractError> {
+    if smoothing.is_zero() || smoothing > Decimal::one() {
+        return Err(ContractError::CustomError {
+            val: "Smoothing must be greater than 0 and at most 1".to_string(),
+        });
+    }
+
+    for dip in multipliers {
+        if dip.below_average.is_zero() || dip.below_average >= Decimal::one() {
+            return Err(ContractError::CustomError {
+                val: "Dip thresholds must be between 0 and 1".to_string(),
+            });
+        }
+        if dip.multiplier.is_zero()
+            || dip.multiplier > Decimal::from_ratio(MAX_DIP_MULTIPLIER, 1u128)
+        {
+            return Err(ContractError::CustomError {
+                val: format!("Dip multipliers must be between 0 and {MAX_DIP_MULTIPLIER}"),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// the multiplier of the deepest dip threshold the price is below the average, 1 if there is no dip
+pub fn get_dip_multiplier(
+    multipliers: &[DipMultiplier],
+    price: Decimal,
+    average_price: Decimal,
+) -> Decimal {
+    if average_price.is_zero() || price >= average_price {
+        return Decimal::one();
+    }
+
+    let dip = Decimal::one() - price / average_price;
+    multipliers
+        .iter()
+        .filter(|d| d.below_average <= dip)
+        .max_by(|a, b| a.below_average.cmp(&b.below_average))
+        .map(|d| d.multiplier)
+        .unwrap_or_else(Decimal::one)
+}
+
+/// the input of every destination for a buy the dip trade: its share of the trade scaled by the
+/// dip multiplier, bounded by the spendable funds so the strategy can never spend what it owes,
+/// and by what is left of `spend_limit` when the strategy caps its total spend
+pub fn get_buy_the_dip_funds(
+    deps: Deps,
+    env: &Env,
+    config: &DcaConfig,
+    state: &State,
+    multipliers: &[DipMultiplier],
+    spend_limit: Option<Uint128>,
+) -> Result<Vec<Coin>, ContractError> {
+    let mut budget = get_spendable_funds(deps, env, config, state)?;
+    if let Some(spend_limit) = spend_limit {
+        budget = budget.min(spend_limit);
+    }
+
+    let mut funds = vec![];
+    for (i, (destination, share)) in config
+        .destinations
+        .iter()
+        .zip(get_destination_funds(config, config.amount_per_trade))
+        .enumerate()
+    {
+        let average_price = state.average_prices.get(i).copied().unwrap_or_default();
+        let multiplier = if average_price.is_zero() {
+            Decimal::one()
+        } else {
+            let price = query_twap_price(deps, config, &destination.denom)?
+                .inv()
+                .expect("twap price is never 0");
+            get_dip_multiplier(multipliers, price, average_price)
+        };
+
+        let amount = (share.amount * multiplier)
+            .checked_add(state.carry_over.get(i).copied().unwrap_or_default())
+            .map_err(StdError::from)?
+            .min(budget);
+        budget -= amount;
+
+        funds.push(Coin {
+            denom: config.source_denom.clone(),
+            amount,
+        });
+    }
+
+    Ok(funds)
+}
+
+/// folds the execution price of every executed swap of a trade into the moving average of its destination
+pub fn update_average_prices(
+    config: &DcaConfig,
+    average_prices: &[Decimal],
+    swap_status: &[SwapEvent],
+    smoothing: Decimal,
+) -> Vec<Decimal> {
+    let mut average_prices: Vec<Decimal> = (0..config.destinations.len())
+        .map(|i| average_prices.get(i).copied().unwrap_or_default())
+        .collect();
+
+    for swap_event in swap_status.iter().filter(|e| e.executed) {
+        let (token_in, token_out) = match (&swap_event.token_in, &swap_event.effective_token_out) {
+            (Some(token_in), Some(token_out)) if !token_out.amount.is_zero() => {
+                (token_in, token_out)
+            }
+            _ => continue,
+        };
+        let i = match config
+            .destinations
+            .iter()
+            .position(|d| d.denom == token_out.denom)
+        {
+            Some(i) => i,
+            None => continue,
+        };
+
+        let price = Decimal::from_ratio(token_in.amount, token_out.amount);
+        average_prices[i] = if average_prices[i].is_zero() {
+            price
+        } else {
+            price * smoothing + average_prices[i] * (Decimal::one() - smoothing)
+        };
+    }
+
+    average_prices
+}
+
+/// key of a swap in the swap history
+pub fn swap_history_key(trade_index: Uint128, destination_index: usize) -> StdResult<(u64, u8)> {
+    let trade_index = u64::try_from(trade_index.u128())
+        .map_err(|_| StdError::generic_err("trade index overflows u64"))?;
+    let destination_index = u8::try_from(destination_index)
+        .map_err(|_| StdError::generic_err("destination index overflows u8"))?;
+
+    Ok((trade_index, destination_index))
+}
+
+/// reply id of a swap, it carries the swap history key of the swap
+/// the flag tells trade swaps and retries apart
+pub fn swap_reply_id(flag: u64, trade_index: Uint128, destination_index: usize) -> StdResult<u64> {
+    let (trade_index, destination_index) = swap_history_key(trade_index, destination_index)?;
+    if trade_index > DCA_SWAP_REPLY_TRADE_MAX {
+        return Err(StdError::generic_err("trade index overflows the reply id"));
+    }
+
+    Ok(flag | trade_index << 8 | u64::from(destination_index))
+}
+
+/// the swap history key carried by a reply id with the given flag, none if the id has another flag
+pub fn parse_swap_reply_id(flag: u64, id: u64) -> Option<(u64, u8)> {
+    let flags = DCA_SWAP_REPLY_FLAG | DCA_RETRY_REPLY_FLAG;
+    if id & flags != flag {
         return None;
     }
 
-    return Some(coin(amount.unwrap(), denom_part.trim()));
+    let id = id & !flags;
+    Some((id >> 8, (id & 0xff) as u8))
+}
+
+/// stores the swap event of a destination in the swap history of its trade
+pub fn save_swap_event(
+    storage: &mut dyn Storage,
+    trade_index: Uint128,
+    destination_index: usize,
+    swap_event: &SwapEvent,
+) -> StdResult<()> {
+    save_swap_history(
+        storage,
+        swap_history_key(trade_index, destination_index)?,
+        swap_event,
+    )
+}
+
+/// records a swap event in the swap history and its index by destination
+pub fn save_swap_history(
+    storage: &mut dyn Storage,
+    (trade_index, destination_index): (u64, u8),
+    swap_event: &SwapEvent,
+) -> StdResult<()> {
+    SWAP_HISTORY.save(storage, (trade_index, destination_index), swap_event)?;
+    SWAP_HISTORY_BY_DESTINATION.save(storage, (destination_index, trade_index), &Empty {})
+}
+
+/// errors unless the strategy can still be executed and changed
+pub fn ensure_active(state: &State) -> Result<(), ContractError> {
+    match state.status {
+        DcaStatus::Active => Ok(()),
+        DcaStatus::Completed => Err(ContractError::DcaCompleted),
+        DcaStatus::Cancelled => Err(ContractError::DcaCancelled),
+        DcaStatus::Settled => Err(ContractError::DcaSettled),
+    }
+}
+
+/// no trades are performed once a strategy is past its end
+pub fn ensure_not_ended(config: &DcaConfig, block: &BlockInfo) -> Result<(), ContractError> {
+    match config.end_at {
+        Some(end_at) if end_at.is_expired(block) => Err(ContractError::DcaEnded { end_at }),
+        _ => Ok(()),
+    }
+}
+
+/// formats coins for response attributes, which can't be empty
+pub fn coins_to_string(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return "0".to_string();
+    }
+
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// adds every coin to the matching denom in the list
+pub fn add_coins(total: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+        match total.iter_mut().find(|total| total.denom == coin.denom) {
+            Some(total) => total.amount += coin.amount,
+            None => total.push(coin.clone()),
+        }
+    }
 }
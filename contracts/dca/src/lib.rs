@@ -1,6 +1,8 @@
 pub mod contract;
+pub mod cron;
 pub mod execute;
-mod helpers;
+pub mod helpers;
+pub mod migrations;
 pub mod query;
 pub mod state;
 
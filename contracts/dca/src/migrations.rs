@@ -0,0 +1,114 @@
+use cosmwasm_std::{StdResult, Storage};
+use cw_storage_plus::Item;
+
+use phase_finance::types::{
+    CatchUpPolicy, CoinWeight, DcaConfig, DcaStatus, ExecutionMode, ExecutorPolicy, FailurePolicy,
+    Schedule, State, StrategyType,
+};
+
+use crate::state::{CONFIG, STATE};
+
+/// storage layout of the contracts deployed with version 0.1.0
+pub mod v0_1_0 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Addr, Decimal, Uint128};
+    use cw_utils::{Duration, Expiration};
+
+    use phase_finance::types::SwapEvent;
+
+    #[cw_serde]
+    pub struct DcaConfig {
+        pub owner: String,
+        pub recipient_address: String,
+        pub executor_address: Addr,
+        pub strategy_type: StrategyType,
+        pub amount_per_trade: Uint128,
+        pub num_trades: Uint128,
+        pub swap_interval: Duration,
+        pub source_denom: String,
+        pub destinations: Vec<CoinWeight>,
+        pub max_slippage: Decimal,
+        pub twap_window_seconds: u64,
+        pub router_contract: Addr,
+    }
+
+    #[cw_serde]
+    pub enum StrategyType {
+        Linear,
+    }
+
+    #[cw_serde]
+    pub struct CoinWeight {
+        pub denom: String,
+        pub weight: Uint128,
+    }
+
+    #[cw_serde]
+    pub struct State {
+        pub next_swap: Expiration,
+        pub paused: bool,
+        pub num_trades_executed: Uint128,
+        pub swap_status: Vec<SwapEvent>,
+    }
+}
+
+const CONFIG_V0_1_0: Item<v0_1_0::DcaConfig> = Item::new("config");
+const STATE_V0_1_0: Item<v0_1_0::State> = Item::new("state");
+
+/// moves config and state from the 0.1.0 layout to the current one, new fields start out empty
+pub fn migrate_from_v0_1_0(storage: &mut dyn Storage) -> StdResult<()> {
+    let config = CONFIG_V0_1_0.load(storage)?;
+    let state = STATE_V0_1_0.load(storage)?;
+
+    let config = DcaConfig {
+        owner: config.owner,
+        recipient_address: config.recipient_address,
+        executor_policy: ExecutorPolicy::Single(config.executor_address),
+        strategy_type: match config.strategy_type {
+            v0_1_0::StrategyType::Linear => StrategyType::Linear,
+        },
+        failure_policy: FailurePolicy::RollOver,
+        execution_mode: ExecutionMode::BestEffort,
+        catch_up_policy: CatchUpPolicy::Skip,
+        executor_tip: None,
+        amount_per_trade: config.amount_per_trade,
+        num_trades: config.num_trades,
+        schedule: Schedule::Interval(config.swap_interval),
+        start_at: None,
+        end_at: None,
+        source_denom: config.source_denom,
+        destinations: config
+            .destinations
+            .into_iter()
+            .map(|d| CoinWeight {
+                denom: d.denom,
+                weight: d.weight,
+                max_price: None,
+            })
+            .collect(),
+        max_slippage: config.max_slippage,
+        twap_window_seconds: config.twap_window_seconds,
+        router_contract: config.router_contract,
+        croncat: None,
+    };
+
+    let status = if state.num_trades_executed >= config.num_trades {
+        DcaStatus::Completed
+    } else {
+        DcaStatus::Active
+    };
+
+    let state = State {
+        status,
+        next_swap: state.next_swap,
+        paused: state.paused,
+        num_trades_executed: state.num_trades_executed,
+        swap_status: state.swap_status,
+        total_bought: vec![],
+        carry_over: vec![],
+        average_prices: vec![],
+    };
+
+    CONFIG.save(storage, &config)?;
+    STATE.save(storage, &state)
+}
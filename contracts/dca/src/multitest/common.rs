@@ -4,38 +4,167 @@ pub use derivative::Derivative;
 pub use crate::contract::{
     execute as executeDCA, instantiate as instantiateDCA, query as queryDCA, reply as replyDCA,
 };
-pub use cosmwasm_std::{coin, BlockInfo, Coin, Decimal, Empty, StdResult, Uint128};
+pub use cosmwasm_std::{
+    coin, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, Decimal, Deps, DepsMut,
+    Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+};
 pub use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+pub use cw_storage_plus::{Item, Map};
 
-pub use cw_utils::Duration;
+pub use cw_utils::{Duration, Expiration};
 
-pub use swaprouter::{
-    contract::{execute as executeRouter, instantiate as instantiateRouter, query as queryRouter},
-    msg::{
-        ExecuteMsg as RouterExecuteMsg, InstantiateMsg as RouterInstantiateMsg,
-        QueryMsg as RouterQueryMsg,
-    },
+pub use swaprouter::msg::{
+    ExecuteMsg as RouterExecuteMsg, GetTwapPriceResponse, InstantiateMsg as RouterInstantiateMsg,
+    QueryMsg as RouterQueryMsg, SwapResponse,
 };
 
 pub use phase_finance::{
+    croncat::{CroncatConfig, ExecuteMsg as CroncatExecuteMsg},
     error::ContractError as DCAContractError,
     msg::{
         ExecuteMsg as DCAExecuteMsg, InstantiateMsg as DCAInstantiateMsg, QueryMsg as DCAQueryMsg,
+        UpdateConfigMsg,
+    },
+    types::{
+        CatchUpPolicy, CoinWeight, DcaConfig, DcaStatus, ExecutionMode, ExecutorPolicy,
+        ExecutorTip, Schedule, State as DCAState, StrategyType, SwapHistoryResponse,
+        UpcomingSwapResponse, UpcomingSwapsResponse,
     },
-    types::{CoinWeight, DcaConfig, State as DCAState, StrategyType, UpcomingSwapResponse},
 };
 
 pub const USER: &str = "user";
 pub const DEPLOYER: &str = "deployer";
 pub const EXECUTOR: &str = "executor";
 pub const DENOM: &str = "uosmo";
+// swaps into this denom always fail in the mock router
+pub const FAIL_DENOM: &str = "ufail";
+// the mock croncat manager keeps this much of a task balance every time it runs the task,
+// more than the fee per trade the croncat tests estimate
+pub const CRONCAT_FEE: u128 = 3;
 
 pub fn contract_dca() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(executeDCA, instantiateDCA, queryDCA).with_reply(replyDCA);
     Box::new(contract)
 }
 
-pub fn contract_router() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(executeRouter, instantiateRouter, queryRouter);
+/// router that swaps 1:1 out of its own balance and answers like the swaprouter does
+pub fn contract_mock_router() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, info: MessageInfo, msg: RouterExecuteMsg| match msg {
+            RouterExecuteMsg::Swap {
+                input_coin,
+                output_denom,
+                ..
+            } => {
+                if output_denom == FAIL_DENOM {
+                    return Err(StdError::generic_err("swap failed"));
+                }
+
+                let response = SwapResponse {
+                    original_sender: info.sender.to_string(),
+                    token_out_denom: output_denom.clone(),
+                    amount: input_coin.amount,
+                };
+
+                Ok(Response::new()
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: vec![coin(input_coin.amount.u128(), output_denom)],
+                    })
+                    .set_data(to_binary(&response)?)
+                    .add_attribute("token_out_amount", input_coin.amount))
+            }
+            _ => Err(StdError::generic_err("not supported by the mock router")),
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: RouterInstantiateMsg| {
+            Ok::<_, StdError>(Response::new())
+        },
+        |_deps: Deps, _env: Env, msg: RouterQueryMsg| -> StdResult<Binary> {
+            match msg {
+                RouterQueryMsg::GetTwapPrice { .. } => to_binary(&GetTwapPriceResponse {
+                    price: Decimal::one(),
+                }),
+                _ => Err(StdError::generic_err("not supported by the mock router")),
+            }
+        },
+    );
+    Box::new(contract)
+}
+
+/// croncat manager that holds the balance of every task and runs all of their actions on ProxyCall,
+/// keeping its fee out of each task balance
+pub fn contract_mock_croncat_manager() -> Box<dyn Contract<Empty>> {
+    const NUM_TASKS: Item<u64> = Item::new("num_tasks");
+    const TASKS: Map<&str, (Addr, Vec<Coin>, Vec<CosmosMsg>)> = Map::new("tasks");
+
+    let contract = ContractWrapper::new(
+        |deps: DepsMut, _env: Env, info: MessageInfo, msg: CroncatExecuteMsg| match msg {
+            CroncatExecuteMsg::CreateTask { task } => {
+                let num_tasks = NUM_TASKS.may_load(deps.storage)?.unwrap_or_default() + 1;
+                NUM_TASKS.save(deps.storage, &num_tasks)?;
+
+                let task_hash = format!("task{num_tasks}");
+                let actions = task.actions.into_iter().map(|a| a.msg).collect();
+                TASKS.save(
+                    deps.storage,
+                    &task_hash,
+                    &(info.sender, info.funds, actions),
+                )?;
+
+                Ok(Response::new().set_data(task_hash.as_bytes()))
+            }
+            CroncatExecuteMsg::RemoveTask { task_hash } => {
+                let (owner, balance, _) = TASKS.load(deps.storage, &task_hash)?;
+                if owner != info.sender {
+                    return Err(StdError::generic_err("not the owner of the task"));
+                }
+                TASKS.remove(deps.storage, &task_hash);
+
+                Ok(Response::new().add_message(BankMsg::Send {
+                    to_address: owner.to_string(),
+                    amount: balance,
+                }))
+            }
+            CroncatExecuteMsg::RefillTaskBalance { task_hash } => {
+                TASKS.update(deps.storage, &task_hash, |task| {
+                    let (owner, mut balance, actions) =
+                        task.ok_or_else(|| StdError::generic_err("unknown task"))?;
+                    for coin in info.funds {
+                        match balance.iter_mut().find(|c| c.denom == coin.denom) {
+                            Some(c) => c.amount += coin.amount,
+                            None => balance.push(coin),
+                        }
+                    }
+                    StdResult::Ok((owner, balance, actions))
+                })?;
+
+                Ok(Response::new())
+            }
+            CroncatExecuteMsg::ProxyCall {} => {
+                let tasks = TASKS
+                    .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                    .collect::<StdResult<Vec<_>>>()?;
+
+                let mut actions = vec![];
+                for (task_hash, (owner, mut balance, task_actions)) in tasks {
+                    for coin in balance.iter_mut() {
+                        coin.amount = coin.amount.checked_sub(Uint128::from(CRONCAT_FEE))?;
+                    }
+                    actions.extend(task_actions.clone());
+                    TASKS.save(deps.storage, &task_hash, &(owner, balance, task_actions))?;
+                }
+
+                Ok(Response::new().add_messages(actions))
+            }
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| {
+            Ok::<_, StdError>(Response::new())
+        },
+        |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> {
+            Err(StdError::generic_err(
+                "not supported by the mock croncat manager",
+            ))
+        },
+    );
     Box::new(contract)
 }
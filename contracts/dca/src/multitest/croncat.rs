@@ -0,0 +1,264 @@
+use crate::multitest::common::*;
+use crate::multitest::suite::*;
+
+use phase_finance::types::ExecutorPolicy;
+
+// 10 trades of 10uosmo and 2uosmo of croncat fees for each of them
+fn init_with_croncat() -> PhaseFinanceSuite {
+    PhaseFinanceSuite::init_with(
+        |msg, croncat_manager| {
+            msg.executor_policy = None;
+            msg.croncat = Some(CroncatConfig {
+                manager_address: croncat_manager.to_string(),
+                fee_per_trade: Uint128::from(2u128),
+                gas_limit: None,
+            });
+        },
+        Some(vec![coin(120, DENOM)]),
+    )
+    .unwrap()
+}
+
+#[test]
+fn instantiate_funds_a_croncat_task() {
+    let suite = init_with_croncat();
+
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(20, DENOM)
+    );
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(100, DENOM));
+    assert_eq!(
+        suite.query_config().unwrap().executor_policy,
+        ExecutorPolicy::Single(manager)
+    );
+}
+
+#[test]
+fn croncat_task_performs_trades() {
+    let mut suite = init_with_croncat();
+    suite.fast_forward_block_time(10);
+
+    // trades come from the manager, not from whoever asked it to run its tasks
+    let executor = suite.executor.clone();
+    let err = suite.try_perform_dca(&executor).unwrap_err();
+    assert_eq!(err, DCAContractError::Unauthorized {});
+    suite.croncat_proxy_call(&executor).unwrap();
+
+    let user = suite.user.clone();
+    assert_eq!(suite.query_balance(&user, "uion").unwrap(), coin(5, "uion"));
+    assert_eq!(
+        suite.query_state().unwrap().num_trades_executed,
+        Uint128::one()
+    );
+}
+
+#[test]
+fn cancel_removes_the_croncat_task() {
+    let mut suite = init_with_croncat();
+    let deployer = suite.deployer.clone();
+    let before = suite.query_balance(&deployer, DENOM).unwrap();
+
+    suite.cancel_dca(&deployer).unwrap();
+
+    // the unspent deposit and the task balance both go back to the owner
+    assert_eq!(
+        suite.query_balance(&deployer, DENOM).unwrap().amount,
+        before.amount + Uint128::from(120u128)
+    );
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(0, DENOM)
+    );
+}
+
+// croncat drops tasks that run out of funds, their balance goes back to the strategy
+fn croncat_removes_the_task(suite: &mut PhaseFinanceSuite) {
+    suite
+        .app
+        .execute_contract(
+            suite.dca.clone(),
+            suite.croncat_manager.clone(),
+            &CroncatExecuteMsg::RemoveTask {
+                task_hash: "task1".to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+}
+
+#[test]
+fn cancel_after_croncat_removed_the_task() {
+    let mut suite = init_with_croncat();
+    croncat_removes_the_task(&mut suite);
+    let deployer = suite.deployer.clone();
+    let before = suite.query_balance(&deployer, DENOM).unwrap();
+
+    suite.cancel_dca(&deployer).unwrap();
+
+    assert_eq!(
+        suite.query_balance(&deployer, DENOM).unwrap().amount,
+        before.amount + Uint128::from(120u128)
+    );
+}
+
+#[test]
+fn deposit_after_croncat_removed_the_task() {
+    let mut suite = init_with_croncat();
+    croncat_removes_the_task(&mut suite);
+    let deployer = suite.deployer.clone();
+
+    suite
+        .app
+        .execute_contract(
+            deployer.clone(),
+            suite.dca.clone(),
+            &DCAExecuteMsg::Deposit {
+                extra_trades: Uint128::from(2u128),
+            },
+            &[coin(24, DENOM)],
+        )
+        .unwrap();
+
+    // the funding of the missing task stays with the strategy and is refunded with the rest
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(0, DENOM)
+    );
+    let before = suite.query_balance(&deployer, DENOM).unwrap();
+    suite.cancel_dca(&deployer).unwrap();
+    assert_eq!(
+        suite.query_balance(&deployer, DENOM).unwrap().amount,
+        before.amount + Uint128::from(144u128)
+    );
+}
+
+#[test]
+fn deposit_refills_the_croncat_task() {
+    let mut suite = init_with_croncat();
+    let deployer = suite.deployer.clone();
+
+    suite
+        .app
+        .execute_contract(
+            deployer,
+            suite.dca.clone(),
+            &DCAExecuteMsg::Deposit {
+                extra_trades: Uint128::from(2u128),
+            },
+            &[coin(24, DENOM)],
+        )
+        .unwrap();
+
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(24, DENOM)
+    );
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(120, DENOM));
+}
+
+#[test]
+fn schedule_change_replaces_the_croncat_task() {
+    let mut suite = init_with_croncat();
+    suite.fast_forward_block_time(10);
+    let executor = suite.executor.clone();
+    suite.croncat_proxy_call(&executor).unwrap();
+
+    let deployer = suite.deployer.clone();
+    suite
+        .update_config(
+            &deployer,
+            UpdateConfigMsg {
+                max_slippage: None,
+                twap_window_seconds: None,
+                executor_policy: None,
+                recipient_address: None,
+                schedule: Some(Schedule::Interval(Duration::Time(60))),
+                router_contract: None,
+                destination_weights: None,
+            },
+        )
+        .unwrap();
+
+    // the old task refunded the 17 tokens croncat didn't keep, they fund the new task
+    // and the 90 tokens of the remaining trades stay with the strategy
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(20, DENOM)
+    );
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(90, DENOM));
+
+    // only the new task performs the next trade
+    suite.fast_forward_block_time(60);
+    suite.croncat_proxy_call(&executor).unwrap();
+    assert_eq!(
+        suite.query_state().unwrap().num_trades_executed,
+        Uint128::from(2u128)
+    );
+}
+
+#[test]
+fn schedule_change_without_a_task_funds_no_new_one() {
+    let mut suite = init_with_croncat();
+    croncat_removes_the_task(&mut suite);
+
+    let deployer = suite.deployer.clone();
+    suite
+        .update_config(
+            &deployer,
+            UpdateConfigMsg {
+                max_slippage: None,
+                twap_window_seconds: None,
+                executor_policy: None,
+                recipient_address: None,
+                schedule: Some(Schedule::Interval(Duration::Time(60))),
+                router_contract: None,
+                destination_weights: None,
+            },
+        )
+        .unwrap();
+
+    // the deposit is owed to the trades, none of it goes to croncat
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(0, DENOM)
+    );
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(120, DENOM));
+}
+
+#[test]
+fn croncat_manager_is_not_tipped() {
+    // croncat is paid with its fee per trade, the tip of 1uosmo per trade stays in the deposit
+    let mut suite = PhaseFinanceSuite::init_with(
+        |msg, croncat_manager| {
+            msg.executor_policy = None;
+            msg.executor_tip = Some(ExecutorTip::Fixed {
+                amount: Uint128::one(),
+            });
+            msg.croncat = Some(CroncatConfig {
+                manager_address: croncat_manager.to_string(),
+                fee_per_trade: Uint128::from(2u128),
+                gas_limit: None,
+            });
+        },
+        Some(vec![coin(130, DENOM)]),
+    )
+    .unwrap();
+    suite.fast_forward_block_time(10);
+
+    let executor = suite.executor.clone();
+    suite.croncat_proxy_call(&executor).unwrap();
+
+    let manager = suite.croncat_manager.clone();
+    assert_eq!(
+        suite.query_balance(&manager, DENOM).unwrap(),
+        coin(20, DENOM)
+    );
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(100, DENOM));
+}
@@ -1,3 +1,5 @@
+use cosmwasm_std::testing::mock_env;
+
 use crate::multitest::common::*;
 use crate::multitest::suite::*;
 
@@ -7,3 +9,459 @@ fn not_executor_cannot_perform_dca() {
     let err = suite.try_perform_dca(&suite.user.clone()).unwrap_err();
     assert_eq!(err, DCAContractError::Unauthorized {});
 }
+
+#[test]
+fn perform_dca_forwards_bought_coins_to_recipient() {
+    let mut suite = PhaseFinanceSuite::init(None, None).unwrap();
+    suite.fast_forward_block_time(10);
+
+    suite.try_perform_dca(&suite.executor.clone()).unwrap();
+
+    // the trade of 10uosmo was split between both destinations and swapped 1:1
+    let user = suite.user.clone();
+    assert_eq!(suite.query_balance(&user, "uion").unwrap(), coin(5, "uion"));
+    assert_eq!(
+        suite.query_balance(&user, "ujuno").unwrap(),
+        coin(5, "ujuno")
+    );
+
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert_eq!(state.total_bought, vec![coin(5, "uion"), coin(5, "ujuno")]);
+
+    let history = suite.query_swap_history().unwrap();
+    assert_eq!(history.swaps.len(), 2);
+    assert!(history.swaps.iter().all(|s| s.swap_event.executed));
+    assert_eq!(
+        history.swaps[0].swap_event.effective_token_out,
+        Some(coin(5, "uion"))
+    );
+}
+
+fn init_with_failing_destination(execution_mode: ExecutionMode) -> PhaseFinanceSuite {
+    PhaseFinanceSuite::init_with(
+        |msg, _| {
+            msg.execution_mode = Some(execution_mode);
+            msg.destinations[1].denom = FAIL_DENOM.to_string();
+        },
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn atomic_trade_reverts_when_a_swap_fails() {
+    let mut suite = init_with_failing_destination(ExecutionMode::Atomic);
+    suite.fast_forward_block_time(10);
+    let next_swap = suite.query_state().unwrap().next_swap;
+
+    // the uion swap succeeds, but the failed ufail swap takes it down with it
+    suite
+        .app
+        .execute_contract(
+            suite.executor.clone(),
+            suite.dca.clone(),
+            &DCAExecuteMsg::PerformDca {},
+            &[],
+        )
+        .unwrap_err();
+
+    let user = suite.user.clone();
+    assert_eq!(suite.query_balance(&user, "uion").unwrap(), coin(0, "uion"));
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::zero());
+    assert_eq!(state.next_swap, next_swap);
+    assert!(suite.query_swap_history().unwrap().swaps.is_empty());
+}
+
+#[test]
+fn best_effort_trade_keeps_the_swaps_that_succeed() {
+    let mut suite = init_with_failing_destination(ExecutionMode::BestEffort);
+    suite.fast_forward_block_time(10);
+
+    suite.try_perform_dca(&suite.executor.clone()).unwrap();
+
+    let user = suite.user.clone();
+    assert_eq!(suite.query_balance(&user, "uion").unwrap(), coin(5, "uion"));
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert_eq!(
+        state.carry_over,
+        vec![Uint128::zero(), Uint128::from(5u128)]
+    );
+}
+
+#[test]
+fn executor_is_tipped_for_every_trade() {
+    // 10 trades of 10uosmo with a tip of 1uosmo each
+    let mut suite = PhaseFinanceSuite::init_with(
+        |msg, _| {
+            msg.executor_tip = Some(ExecutorTip::Fixed {
+                amount: Uint128::one(),
+            })
+        },
+        Some(vec![coin(110, DENOM)]),
+    )
+    .unwrap();
+    suite.fast_forward_block_time(10);
+
+    let executor = suite.executor.clone();
+    let before = suite.query_balance(&executor, DENOM).unwrap();
+    suite.try_perform_dca(&executor).unwrap();
+    assert_eq!(
+        suite.query_balance(&executor, DENOM).unwrap().amount,
+        before.amount + Uint128::one()
+    );
+
+    // withdrawn trades take their tips along
+    let deployer = suite.deployer.clone();
+    let before = suite.query_balance(&deployer, DENOM).unwrap();
+    suite
+        .app
+        .execute_contract(
+            deployer.clone(),
+            suite.dca.clone(),
+            &DCAExecuteMsg::Withdraw {
+                trades: Uint128::from(2u128),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.query_balance(&deployer, DENOM).unwrap().amount,
+        before.amount + Uint128::from(22u128)
+    );
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(77, DENOM));
+}
+
+#[test]
+fn executor_is_not_tipped_when_nothing_was_bought() {
+    // a single destination whose swaps always fail
+    let mut suite = PhaseFinanceSuite::init_with(
+        |msg, _| {
+            msg.executor_tip = Some(ExecutorTip::Fixed {
+                amount: Uint128::one(),
+            });
+            msg.destinations.truncate(1);
+            msg.destinations[0].denom = FAIL_DENOM.to_string();
+        },
+        Some(vec![coin(110, DENOM)]),
+    )
+    .unwrap();
+    suite.fast_forward_block_time(10);
+
+    let executor = suite.executor.clone();
+    let before = suite.query_balance(&executor, DENOM).unwrap();
+    suite.try_perform_dca(&executor).unwrap();
+    assert_eq!(suite.query_balance(&executor, DENOM).unwrap(), before);
+
+    // the trade still counts and its input is rolled over, the tip stays in the deposit
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert_eq!(state.carry_over, vec![Uint128::from(10u128)]);
+    assert_eq!(suite.query_bonded_funds().unwrap(), coin(110, DENOM));
+}
+
+#[test]
+fn height_interval_schedules_trades_in_blocks() {
+    let mut suite = PhaseFinanceSuite::init_with(
+        |msg, _| msg.schedule = Schedule::Interval(Duration::Height(5)),
+        None,
+    )
+    .unwrap();
+    let height = suite.app.block_info().height;
+
+    let upcoming = suite.query_upcoming_swap().unwrap();
+    assert_eq!(upcoming.next_swap, Expiration::AtHeight(height + 5));
+    assert!(!upcoming.can_execute);
+
+    let executor = suite.executor.clone();
+    let err = suite.try_perform_dca(&executor).unwrap_err();
+    assert_eq!(
+        err,
+        DCAContractError::DcaSwapNotAllowedYet {
+            next_swap: Expiration::AtHeight(height + 5)
+        }
+    );
+
+    suite.fast_forward_block_height(5);
+    assert!(suite.query_upcoming_swap().unwrap().can_execute);
+    suite.try_perform_dca(&executor).unwrap();
+
+    // the 9 trades left are 5 blocks apart, starting 5 blocks after the last one
+    let upcoming = suite.query_all_upcoming_swaps(None, None).unwrap().swaps;
+    assert_eq!(upcoming.len(), 9);
+    assert_eq!(upcoming[0].next_swap, Expiration::AtHeight(height + 10));
+    assert_eq!(upcoming[8].next_swap, Expiration::AtHeight(height + 50));
+    assert!(upcoming.iter().all(|swap| !swap.can_execute));
+}
+
+#[test]
+fn time_interval_schedules_trades_in_seconds() {
+    let suite = PhaseFinanceSuite::init(None, None).unwrap();
+    let time = suite.app.block_info().time;
+
+    let upcoming = suite.query_all_upcoming_swaps(None, None).unwrap().swaps;
+    assert_eq!(upcoming.len(), 10);
+    assert_eq!(
+        upcoming
+            .iter()
+            .map(|swap| swap.next_swap)
+            .collect::<Vec<_>>(),
+        (1..=10)
+            .map(|i| Expiration::AtTime(time.plus_seconds(i)))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn strategy_runs_between_start_and_end_then_anyone_settles_it() {
+    let start = mock_env().block.time.plus_seconds(100);
+    let end = start.plus_seconds(10);
+    let mut suite = PhaseFinanceSuite::init_with(
+        |msg, _| {
+            msg.start_at = Some(Expiration::AtTime(start));
+            msg.end_at = Some(Expiration::AtTime(end));
+        },
+        None,
+    )
+    .unwrap();
+    let executor = suite.executor.clone();
+    let anyone = Addr::unchecked("anyone");
+
+    // the first trade waits for the start, later ones follow the interval
+    let upcoming = suite.query_all_upcoming_swaps(None, None).unwrap().swaps;
+    assert_eq!(upcoming[0].next_swap, Expiration::AtTime(start));
+    // trades due from the end on are never performed
+    assert_eq!(upcoming.len(), 10);
+    suite.fast_forward_block_time(50);
+    let err = suite.try_perform_dca(&executor).unwrap_err();
+    assert_eq!(
+        err,
+        DCAContractError::DcaSwapNotAllowedYet {
+            next_swap: Expiration::AtTime(start)
+        }
+    );
+
+    suite.fast_forward_block_time(50);
+    suite.try_perform_dca(&executor).unwrap();
+    let upcoming = suite.query_all_upcoming_swaps(None, None).unwrap().swaps;
+    assert_eq!(upcoming.len(), 9);
+    assert_eq!(
+        suite.settle_dca(&anyone).unwrap_err(),
+        DCAContractError::DcaNotEnded
+    );
+
+    suite.fast_forward_block_time(10);
+    let ended = DCAContractError::DcaEnded {
+        end_at: Expiration::AtTime(end),
+    };
+    assert_eq!(suite.try_perform_dca(&executor).unwrap_err(), ended);
+
+    // an ended strategy can only be settled, not extended or changed
+    let deployer = suite.deployer.clone();
+    let err: DCAContractError = suite
+        .app
+        .execute_contract(
+            deployer.clone(),
+            suite.dca.clone(),
+            &DCAExecuteMsg::Deposit {
+                extra_trades: Uint128::one(),
+            },
+            &[coin(10, DENOM)],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ended);
+    let update = UpdateConfigMsg {
+        max_slippage: Some(Decimal::percent(2)),
+        twap_window_seconds: None,
+        executor_policy: None,
+        recipient_address: None,
+        schedule: None,
+        router_contract: None,
+        destination_weights: None,
+    };
+    assert_eq!(suite.update_config(&deployer, update).unwrap_err(), ended);
+    suite.pause_dca(&deployer).unwrap();
+    assert_eq!(suite.resume_dca(&deployer).unwrap_err(), ended);
+
+    assert!(suite
+        .query_all_upcoming_swaps(None, None)
+        .unwrap()
+        .swaps
+        .is_empty());
+
+    // the 90 source tokens left go back to the owner
+    let owner_balance = suite.query_balance(&deployer, DENOM).unwrap().amount;
+    suite.settle_dca(&anyone).unwrap();
+    assert_eq!(
+        suite.query_balance(&deployer, DENOM).unwrap().amount,
+        owner_balance + Uint128::from(90u128)
+    );
+    let dca = suite.dca.clone();
+    assert_eq!(
+        suite.query_balance(&dca, DENOM).unwrap().amount,
+        Uint128::zero()
+    );
+    assert_eq!(suite.query_state().unwrap().status, DcaStatus::Settled);
+
+    assert_eq!(
+        suite.settle_dca(&anyone).unwrap_err(),
+        DCAContractError::DcaSettled
+    );
+    assert_eq!(
+        suite.cancel_dca(&deployer).unwrap_err(),
+        DCAContractError::DcaSettled
+    );
+}
+
+#[test]
+fn schedule_change_keeps_the_start_and_end() {
+    let start = mock_env().block.time.plus_seconds(100);
+    let end = start.plus_seconds(100);
+    let mut suite = PhaseFinanceSuite::init_with(
+        |msg, _| {
+            msg.start_at = Some(Expiration::AtTime(start));
+            msg.end_at = Some(Expiration::AtTime(end));
+        },
+        None,
+    )
+    .unwrap();
+    let deployer = suite.deployer.clone();
+    let executor = suite.executor.clone();
+    let update = |schedule| UpdateConfigMsg {
+        max_slippage: None,
+        twap_window_seconds: None,
+        executor_policy: None,
+        recipient_address: None,
+        schedule: Some(schedule),
+        router_contract: None,
+        destination_weights: None,
+    };
+
+    // a new schedule before the start still waits for it
+    suite
+        .update_config(&deployer, update(Schedule::Interval(Duration::Time(5))))
+        .unwrap();
+    assert_eq!(
+        suite.query_upcoming_swap().unwrap().next_swap,
+        Expiration::AtTime(start)
+    );
+    suite.fast_forward_block_time(10);
+    assert_eq!(
+        suite.try_perform_dca(&executor).unwrap_err(),
+        DCAContractError::DcaSwapNotAllowedYet {
+            next_swap: Expiration::AtTime(start)
+        }
+    );
+
+    // start and end are times, so the schedule can't switch to heights
+    assert_eq!(
+        suite
+            .update_config(&deployer, update(Schedule::Interval(Duration::Height(5))))
+            .unwrap_err(),
+        DCAContractError::CustomError {
+            val: "Start and end must be heights or times like the schedule".to_string()
+        }
+    );
+
+    // once started, the new schedule runs from now on
+    suite.fast_forward_block_time(100);
+    suite.try_perform_dca(&executor).unwrap();
+    suite
+        .update_config(&deployer, update(Schedule::Interval(Duration::Time(10))))
+        .unwrap();
+    let now = suite.app.block_info().time;
+    assert_eq!(
+        suite.query_upcoming_swap().unwrap().next_swap,
+        Expiration::AtTime(now.plus_seconds(10))
+    );
+}
+
+fn init_with_catch_up_policy(catch_up_policy: CatchUpPolicy) -> PhaseFinanceSuite {
+    PhaseFinanceSuite::init_with(
+        |msg, _| {
+            msg.catch_up_policy = Some(catch_up_policy);
+            msg.schedule = Schedule::Interval(Duration::Time(10));
+        },
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn skipped_trades_slide_forward() {
+    let mut suite = init_with_catch_up_policy(CatchUpPolicy::Skip);
+    let executor = suite.executor.clone();
+    // the executor was away for 4 trades
+    suite.fast_forward_block_time(40);
+    let now = suite.app.block_info().time;
+
+    suite.try_perform_dca(&executor).unwrap();
+
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert_eq!(state.next_swap, Expiration::AtTime(now.plus_seconds(10)));
+}
+
+#[test]
+fn batched_trades_catch_up_in_one_call() {
+    let mut suite = init_with_catch_up_policy(CatchUpPolicy::Batch { max_trades: 3 });
+    let executor = suite.executor.clone();
+    let user = suite.user.clone();
+    let start = suite.app.block_info().time;
+    // trades were due at 10, 20, 30 and 40 seconds
+    suite.fast_forward_block_time(45);
+
+    // the first 3 of them are swapped together
+    suite.try_perform_dca(&executor).unwrap();
+    assert_eq!(
+        suite.query_balance(&user, "uion").unwrap(),
+        coin(15, "uion")
+    );
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::from(3u128));
+    assert_eq!(state.next_swap, Expiration::AtTime(start.plus_seconds(40)));
+
+    // the one left over is still due, after it the schedule is back on its cadence
+    suite.try_perform_dca(&executor).unwrap();
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::from(4u128));
+    assert_eq!(state.next_swap, Expiration::AtTime(start.plus_seconds(50)));
+    assert_eq!(
+        suite.try_perform_dca(&executor).unwrap_err(),
+        DCAContractError::DcaSwapNotAllowedYet {
+            next_swap: Expiration::AtTime(start.plus_seconds(50))
+        }
+    );
+}
+
+#[test]
+fn anchored_trades_keep_their_cadence() {
+    let mut suite = init_with_catch_up_policy(CatchUpPolicy::Anchor);
+    let executor = suite.executor.clone();
+    let start = suite.app.block_info().time;
+    suite.fast_forward_block_time(25);
+
+    // the trades due at 10 and 20 seconds are performed one after the other
+    suite.try_perform_dca(&executor).unwrap();
+    assert_eq!(
+        suite.query_state().unwrap().next_swap,
+        Expiration::AtTime(start.plus_seconds(20))
+    );
+    suite.try_perform_dca(&executor).unwrap();
+
+    let state = suite.query_state().unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::from(2u128));
+    assert_eq!(state.next_swap, Expiration::AtTime(start.plus_seconds(30)));
+    suite.try_perform_dca(&executor).unwrap_err();
+
+    suite.fast_forward_block_time(5);
+    suite.try_perform_dca(&executor).unwrap();
+    assert_eq!(
+        suite.query_state().unwrap().next_swap,
+        Expiration::AtTime(start.plus_seconds(40))
+    );
+}
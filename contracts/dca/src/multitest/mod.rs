@@ -1,3 +1,4 @@
 pub mod common;
+pub mod croncat;
 pub mod dca;
 pub mod suite;
@@ -1,5 +1,4 @@
 use crate::multitest::common::*;
-use cosmwasm_std::Addr;
 use cw_multi_test::App;
 
 #[derive(Derivative)]
@@ -17,6 +16,8 @@ pub struct PhaseFinanceSuite {
     pub dca: Addr,
     // router address
     pub router: Addr,
+    // mock croncat manager address
+    pub croncat_manager: Addr,
 }
 
 impl PhaseFinanceSuite {
@@ -24,7 +25,27 @@ impl PhaseFinanceSuite {
         init_msg: Option<DCAInstantiateMsg>,
         funds: Option<Vec<Coin>>,
     ) -> Result<PhaseFinanceSuite> {
-        let genesis_funds = vec![coin(150000, DENOM)];
+        Self::init_with(
+            |msg, _| {
+                if let Some(init_msg) = init_msg {
+                    *msg = init_msg;
+                }
+            },
+            funds,
+        )
+    }
+
+    /// instantiates the default strategy after `update` changed it, the router is known by then
+    /// and the address of the croncat manager is passed along
+    pub fn init_with(
+        update: impl FnOnce(&mut DCAInstantiateMsg, &Addr),
+        funds: Option<Vec<Coin>>,
+    ) -> Result<PhaseFinanceSuite> {
+        let genesis_funds = vec![
+            coin(150000, DENOM),
+            coin(100000, "uion"),
+            coin(100000, "ujuno"),
+        ];
         let deployer = Addr::unchecked(DEPLOYER);
         let executor = Addr::unchecked(EXECUTOR);
         let user = Addr::unchecked(USER);
@@ -38,7 +59,7 @@ impl PhaseFinanceSuite {
         app.send_tokens(deployer.clone(), executor.clone(), &[coin(50000, DENOM)])?;
 
         let dca_id = app.store_code(contract_dca());
-        let router_id = app.store_code(contract_router());
+        let router_id = app.store_code(contract_mock_router());
 
         let router = app
             .instantiate_contract(
@@ -52,35 +73,66 @@ impl PhaseFinanceSuite {
                 Some(deployer.to_string()),
             )
             .unwrap();
+        app.send_tokens(
+            deployer.clone(),
+            router.clone(),
+            &[coin(100000, "uion"), coin(100000, "ujuno")],
+        )?;
+
+        let croncat_manager_id = app.store_code(contract_mock_croncat_manager());
+        let croncat_manager = app
+            .instantiate_contract(
+                croncat_manager_id,
+                deployer.clone(),
+                &Empty {},
+                &[],
+                "croncat_manager",
+                None,
+            )
+            .unwrap();
+
+        let mut init_msg = DCAInstantiateMsg {
+            owner: None,
+            factory: None,
+            recipient_address: user.to_string(),
+            executor_policy: Some(ExecutorPolicy::Single(executor.clone())),
+            router_contract: router.to_string(),
+            strategy_type: StrategyType::Linear,
+            failure_policy: None,
+            execution_mode: None,
+            catch_up_policy: None,
+            executor_tip: None,
+            max_slippage: Decimal::from_ratio(1u128, 100u128),
+            twap_window_seconds: 1,
+            num_trades: Uint128::from(10u128),
+            schedule: Schedule::Interval(Duration::Time(1)),
+            start_at: None,
+            end_at: None,
+            source_denom: DENOM.to_string(),
+            amount_per_trade: Uint128::from(10u128),
+            destinations: vec![
+                CoinWeight {
+                    denom: "uion".to_string(),
+                    weight: Uint128::from(100u128),
+                    max_price: None,
+                },
+                CoinWeight {
+                    denom: "ujuno".to_string(),
+                    weight: Uint128::from(100u128),
+                    max_price: None,
+                },
+            ],
+            platform_fee: Uint128::zero(),
+            platform_fee_recipient: "osmo123".to_string(),
+            croncat: None,
+        };
+        update(&mut init_msg, &croncat_manager);
 
         let dca = app
             .instantiate_contract(
                 dca_id,
                 deployer.clone(),
-                &init_msg.unwrap_or(DCAInstantiateMsg {
-                    recipient_address: user.to_string(),
-                    executor_address: Some(executor.to_string()),
-                    router_contract: router.to_string(),
-                    strategy_type: StrategyType::Linear,
-                    max_slippage: Decimal::from_ratio(1u128, 100u128),
-                    twap_window_seconds: 1,
-                    num_trades: Uint128::from(10u128),
-                    swap_interval: Duration::Time(1),
-                    source_denom: DENOM.to_string(),
-                    amount_per_trade: Uint128::from(10u128),
-                    destinations: vec![
-                        CoinWeight {
-                            denom: "uion".to_string(),
-                            weight: Uint128::from(100u128),
-                        },
-                        CoinWeight {
-                            denom: "ujuno".to_string(),
-                            weight: Uint128::from(100u128),
-                        },
-                    ],
-                    platform_fee: Uint128::zero(),
-                    platform_fee_recipient: "osmo123".to_string(),
-                }),
+                &init_msg,
                 &funds.unwrap_or(vec![coin(100, DENOM)]),
                 "dca_contract",
                 Some(deployer.to_string()), // admin: Option<String>, will need this for upgrading
@@ -94,11 +146,12 @@ impl PhaseFinanceSuite {
             deployer,
             router,
             dca,
+            croncat_manager,
         })
     }
 
-    pub fn query_balance(&self, addr: &Addr) -> StdResult<Coin> {
-        Ok(self.app.wrap().query_balance(addr.as_str(), "uosmo")?)
+    pub fn query_balance(&self, addr: &Addr, denom: &str) -> StdResult<Coin> {
+        self.app.wrap().query_balance(addr.as_str(), denom)
     }
 
     pub fn cancel_dca(&mut self, sender: &Addr) -> Result<(), DCAContractError> {
@@ -109,6 +162,14 @@ impl PhaseFinanceSuite {
             .map(|_| ())
     }
 
+    pub fn settle_dca(&mut self, sender: &Addr) -> Result<(), DCAContractError> {
+        let msg = DCAExecuteMsg::SettleDca {};
+        self.app
+            .execute_contract(sender.clone(), self.dca.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
     pub fn resume_dca(&mut self, sender: &Addr) -> Result<(), DCAContractError> {
         let msg = DCAExecuteMsg::ResumeDca {};
         self.app
@@ -125,6 +186,18 @@ impl PhaseFinanceSuite {
             .map(|_| ())
     }
 
+    pub fn update_config(
+        &mut self,
+        sender: &Addr,
+        msg: UpdateConfigMsg,
+    ) -> Result<(), DCAContractError> {
+        let msg = DCAExecuteMsg::UpdateConfig(msg);
+        self.app
+            .execute_contract(sender.clone(), self.dca.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
     pub fn try_perform_dca(&mut self, sender: &Addr) -> Result<(), DCAContractError> {
         let msg = DCAExecuteMsg::PerformDca {};
         self.app
@@ -133,13 +206,27 @@ impl PhaseFinanceSuite {
             .map(|_| ())
     }
 
+    /// an agent asking the croncat manager to execute its tasks
+    pub fn croncat_proxy_call(&mut self, sender: &Addr) -> Result<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            self.croncat_manager.clone(),
+            &CroncatExecuteMsg::ProxyCall {},
+            &[],
+        )
+    }
+
     pub fn query_upcoming_swap(&self) -> StdResult<UpcomingSwapResponse> {
         let msg = DCAQueryMsg::GetUpcomingSwap {};
         self.app.wrap().query_wasm_smart(self.dca.clone(), &msg)
     }
 
-    pub fn query_all_upcoming_swaps(&self) -> StdResult<Vec<UpcomingSwapResponse>> {
-        let msg = DCAQueryMsg::GetAllUpcomingSwaps {};
+    pub fn query_all_upcoming_swaps(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<UpcomingSwapsResponse> {
+        let msg = DCAQueryMsg::GetAllUpcomingSwaps { start_after, limit };
         self.app.wrap().query_wasm_smart(self.dca.clone(), &msg)
     }
 
@@ -158,6 +245,15 @@ impl PhaseFinanceSuite {
         self.app.wrap().query_wasm_smart(self.dca.clone(), &msg)
     }
 
+    pub fn query_swap_history(&self) -> StdResult<SwapHistoryResponse> {
+        let msg = DCAQueryMsg::SwapHistory {
+            start_after: None,
+            limit: None,
+            destination: None,
+        };
+        self.app.wrap().query_wasm_smart(self.dca.clone(), &msg)
+    }
+
     pub fn query_config(&self) -> StdResult<DcaConfig> {
         let msg = DCAQueryMsg::Config {};
         self.app.wrap().query_wasm_smart(self.dca.clone(), &msg)
@@ -174,4 +270,16 @@ impl PhaseFinanceSuite {
 
         self.app.set_block(mock_block);
     }
+
+    pub fn fast_forward_block_height(&mut self, blocks: u64) {
+        let block = self.app.block_info();
+
+        let mock_block = BlockInfo {
+            height: block.height + blocks,
+            chain_id: block.chain_id,
+            time: block.time.plus_seconds(blocks * 5),
+        };
+
+        self.app.set_block(mock_block);
+    }
 }
@@ -1,56 +1,105 @@
-use std::ops::{Add, Mul};
-
-use cosmwasm_std::{Coin, Deps, Env, StdResult};
-
-use cw_utils::Duration;
-use phase_finance::types::{DcaConfig, State, UpcomingSwapResponse};
-
-use crate::{
-    helpers::get_expiration_time,
-    state::{CONFIG, STATE},
+use cosmwasm_std::{Coin, Deps, Env, Order, StdError, StdResult, Uint128};
+use cw_storage_plus::Bound;
+use cw_utils::Expiration;
+
+use phase_finance::constants::{DEFAULT_LIMIT, MAX_LIMIT};
+use phase_finance::types::{
+    DcaConfig, DcaStatus, State, SwapHistoryEntry, SwapHistoryResponse, UpcomingSwapResponse,
+    UpcomingSwapsResponse,
 };
 
+use crate::helpers::{get_destination_funds, get_later_swap, get_trade_amount};
+use crate::state::{CONFIG, STATE, SWAP_HISTORY, SWAP_HISTORY_BY_DESTINATION};
+
 pub fn query_upcoming_swap(deps: Deps, env: Env) -> StdResult<UpcomingSwapResponse> {
+    let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
 
-    Ok(UpcomingSwapResponse {
-        next_swap: get_expiration_time(state.next_swap),
-        can_execute: state.next_swap.is_expired(&env.block),
-    })
+    get_upcoming_swap(
+        &env,
+        &config,
+        &state,
+        state.num_trades_executed.u128() as u64,
+        state.next_swap,
+    )
 }
 
-pub fn query_all_upcoming_swaps(deps: Deps, env: Env) -> StdResult<Vec<UpcomingSwapResponse>> {
+/// every trade left, a strategy that finished, ended or was cancelled or settled has none
+pub fn query_all_upcoming_swaps(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<UpcomingSwapsResponse> {
     let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+
+    if state.status != DcaStatus::Active
+        || config
+            .end_at
+            .is_some_and(|end_at| end_at.is_expired(&env.block))
+    {
+        return Ok(UpcomingSwapsResponse { swaps: vec![] });
+    }
+
+    let first = state.num_trades_executed.u128() as u64;
+    let start = start_after.map_or(first, |index| first.max(index.saturating_add(1)));
+    let end = (config.num_trades.u128() as u64).min(start.saturating_add(limit));
+    if start >= end {
+        return Ok(UpcomingSwapsResponse { swaps: vec![] });
+    }
+
+    // every trade left is due when the schedule next fires after the one before it,
+    // those due past the end are never performed
+    let mut next_swap = get_later_swap(&config.schedule, state.next_swap, start - first)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let mut swaps = vec![];
+    for trade_index in start..end {
+        if trade_index > start {
+            next_swap = get_later_swap(&config.schedule, next_swap, 1)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+        }
+        if config.end_at.is_some_and(|end_at| next_swap >= end_at) {
+            break;
+        }
+        swaps.push(get_upcoming_swap(
+            &env,
+            &config,
+            &state,
+            trade_index,
+            next_swap,
+        )?);
+    }
+
+    Ok(UpcomingSwapsResponse { swaps })
+}
+
+/// the next trade also swaps what was carried over from skipped ones
+fn get_upcoming_swap(
+    env: &Env,
+    config: &DcaConfig,
+    state: &State,
+    trade_index: u64,
+    next_swap: Expiration,
+) -> StdResult<UpcomingSwapResponse> {
+    let trades_ahead = trade_index - state.num_trades_executed.u128() as u64;
+    let amount = get_trade_amount(config, Uint128::from(trade_index))
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let mut amounts = get_destination_funds(config, amount);
+    if trades_ahead == 0 {
+        for (i, funds) in amounts.iter_mut().enumerate() {
+            funds.amount += state.carry_over.get(i).copied().unwrap_or_default();
+        }
+    }
 
-    let pending_swaps = config.num_trades - state.num_trades_executed;
-
-    let upcoming_swaps: Vec<UpcomingSwapResponse> =
-        Vec::<UpcomingSwapResponse>::with_capacity(pending_swaps.u128() as usize)
-            .iter()
-            .enumerate()
-            .map(|(i, _)| {
-                if i > 0 {
-                    let index = i + 1;
-                    let interval_in_seconds = match config.swap_interval {
-                        Duration::Time(time) => time,
-                        _ => 0,
-                    };
-                    return UpcomingSwapResponse {
-                        next_swap: get_expiration_time(state.next_swap)
-                            .add(interval_in_seconds.mul(index as u64)),
-                        can_execute: false,
-                    };
-                }
-
-                UpcomingSwapResponse {
-                    next_swap: get_expiration_time(state.next_swap),
-                    can_execute: state.next_swap.is_expired(&env.block),
-                }
-            })
-            .collect();
-
-    Ok(upcoming_swaps)
+    Ok(UpcomingSwapResponse {
+        trade_index,
+        next_swap,
+        can_execute: trades_ahead == 0 && next_swap.is_expired(&env.block),
+        amounts,
+    })
 }
 
 pub fn query_bonded_funds(deps: Deps, env: Env) -> StdResult<Coin> {
@@ -89,3 +138,71 @@ pub fn query_config(deps: Deps) -> StdResult<DcaConfig> {
 pub fn query_state(deps: Deps) -> StdResult<State> {
     STATE.load(deps.storage)
 }
+
+pub fn query_swap_history(
+    deps: Deps,
+    start_after: Option<(u64, u8)>,
+    limit: Option<u32>,
+    destination: Option<String>,
+) -> StdResult<SwapHistoryResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let to_entry = |(trade_index, destination_index): (u64, u8), swap_event| SwapHistoryEntry {
+        trade_index,
+        destination_index,
+        destination_denom: config.destinations[destination_index as usize]
+            .denom
+            .clone(),
+        swap_event,
+    };
+
+    let destination = match destination {
+        Some(destination) => destination,
+        None => {
+            let swaps = SWAP_HISTORY
+                .range(
+                    deps.storage,
+                    start_after.map(Bound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .take(limit)
+                .map(|item| item.map(|(key, swap_event)| to_entry(key, swap_event)))
+                .collect::<StdResult<Vec<_>>>()?;
+
+            return Ok(SwapHistoryResponse { swaps });
+        }
+    };
+
+    // the bought denoms never change, so a destination keeps its index
+    let destination_index = match config
+        .destinations
+        .iter()
+        .position(|d| d.denom == destination)
+    {
+        Some(i) => i as u8,
+        None => return Ok(SwapHistoryResponse { swaps: vec![] }),
+    };
+
+    // the cursor can point at a swap of another destination of the same trade
+    let min = start_after.map(|(trade_index, start_destination_index)| {
+        if destination_index > start_destination_index {
+            Bound::inclusive(trade_index)
+        } else {
+            Bound::exclusive(trade_index)
+        }
+    });
+
+    let swaps = SWAP_HISTORY_BY_DESTINATION
+        .prefix(destination_index)
+        .keys(deps.storage, min, None, Order::Ascending)
+        .take(limit)
+        .map(|trade_index| {
+            let key = (trade_index?, destination_index);
+            Ok(to_entry(key, SWAP_HISTORY.load(deps.storage, key)?))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(SwapHistoryResponse { swaps })
+}
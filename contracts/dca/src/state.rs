@@ -1,7 +1,26 @@
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Addr, Coin, Empty, Uint128};
 use cw_storage_plus::{Item, Map};
-use phase_finance::types::{DcaConfig, State};
+use phase_finance::types::{DcaConfig, State, SwapEvent};
 
 pub const CONFIG: Item<DcaConfig> = Item::new("config");
 pub const STATE: Item<State> = Item::new("state");
 pub const BONDED_BALANCES: Map<String, Uint128> = Map::new("bonded_balances");
+// every swap event, keyed by trade index and destination index
+pub const SWAP_HISTORY: Map<(u64, u8), SwapEvent> = Map::new("swap_history");
+// the swap history keys again, by destination index first so one destination can be paged through
+pub const SWAP_HISTORY_BY_DESTINATION: Map<(u8, u64), Empty> =
+    Map::new("swap_history_by_destination");
+// input of the swaps waiting for their reply, keyed like the swap history
+pub const PENDING_SWAPS: Map<(u64, u8), Coin> = Map::new("pending_swaps");
+// factory that created the strategy, told about recipient changes
+pub const FACTORY: Item<Addr> = Item::new("factory");
+// hash of the croncat task performing the trades
+pub const CRONCAT_TASK_HASH: Item<String> = Item::new("croncat_task_hash");
+// source balance before the croncat task of an old schedule was removed, what it refunds funds the new task
+pub const REPLACED_TASK_BALANCE: Item<Uint128> = Item::new("replaced_task_balance");
+// input of the failed swaps waiting to be retried, keyed like the swap history
+pub const RETRY_QUEUE: Map<(u64, u8), Coin> = Map::new("retry_queue");
+// executor and tip of the trade being performed, paid once the trade bought something
+pub const PENDING_TIP: Item<(Addr, Uint128)> = Item::new("pending_tip");
+// source tokens swapped so far, bounds a buy the dip strategy with a max total spend
+pub const TOTAL_SPENT: Item<Uint128> = Item::new("total_spent");
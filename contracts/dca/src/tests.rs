@@ -1,19 +1,37 @@
+use std::str::FromStr;
+
 use cosmwasm_std::testing::{
     mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
 };
 use cosmwasm_std::{
-    coins, from_binary, BlockInfo, Coin, Decimal, Env, OwnedDeps, StdResult, Timestamp, Uint128,
+    coins, from_binary, to_binary, Addr, Attribute, BankMsg, BlockInfo, Coin, ContractResult,
+    CosmosMsg, Decimal, Env, OwnedDeps, Reply, Response, StdResult, Storage, SubMsgResponse,
+    SubMsgResult, SystemResult, Timestamp, Uint128, WasmMsg, WasmQuery,
 };
 
+use cw2::{get_contract_version, set_contract_version};
 use cw_denom::DenomError;
-use cw_utils::Duration;
-use phase_finance::types::{CoinWeight, StrategyType};
+use cw_utils::{Duration, Expiration};
+use phase_finance::constants::{DCA_RETRY_REPLY_FLAG, DCA_SWAP_REPLY_FLAG};
+use phase_finance::croncat::{Boundary, Interval};
+use phase_finance::error::ContractError;
+use phase_finance::types::{
+    CatchUpPolicy, CoinWeight, DcaStatus, DipMultiplier, ExecutionMode, ExecutorPolicy,
+    ExecutorTip, FailurePolicy, Schedule, StrategyType, SwapEvent, SwapHistoryResponse,
+    UpcomingSwapsResponse,
+};
 
-use crate::contract::{execute, instantiate, query};
-use crate::helpers::token_string_to_coin;
-use crate::state::STATE;
+use crate::contract::{execute, instantiate, migrate, query, reply};
+use crate::cron::CronSchedule;
+use crate::helpers::{
+    get_croncat_boundary, get_croncat_interval, get_destination_funds, get_dip_multiplier,
+    get_later_swap, get_total_deposit, parse_swap_reply_id, swap_reply_id, update_average_prices,
+    validate_dca_config,
+};
+use crate::state::{CONFIG, RETRY_QUEUE, STATE, TOTAL_SPENT};
 
-use phase_finance::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use phase_finance::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, UpdateConfigMsg};
+use swaprouter::msg::{GetTwapPriceResponse, QueryMsg as RouterQueryMsg};
 
 pub const ADMIN_ADDR: &str = "admin_addr";
 pub const EXECUTOR_ADDR: &str = "executor";
@@ -24,28 +42,39 @@ fn do_instantiate() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
     let env = mock_env();
 
     let instantiate_msg = InstantiateMsg {
+        owner: None,
+        factory: None,
         recipient_address: "osmo123".to_string(),
-        executor_address: Some(EXECUTOR_ADDR.to_string()),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
         strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
         destinations: vec![
             CoinWeight {
                 denom: "uion".to_string(),
                 weight: Uint128::from(100u128),
+                max_price: None,
             },
             CoinWeight {
                 denom: "ujuno".to_string(),
                 weight: Uint128::from(100u128),
+                max_price: None,
             },
         ],
         max_slippage: Decimal::from_ratio(1u128, 100u128),
         twap_window_seconds: 1,
         amount_per_trade: Uint128::from(10u128),
         num_trades: Uint128::from(10u128),
-        swap_interval: Duration::Time(1),
+        schedule: Schedule::Interval(Duration::Time(1)),
+        start_at: None,
+        end_at: None,
         source_denom: "uosmo".to_string(),
         router_contract: "osmoabc".to_string(),
         platform_fee: Uint128::zero(),
         platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
     };
 
     instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap();
@@ -60,7 +89,7 @@ fn fast_forward_time(mut env: Env, time: u64) -> Env {
         chain_id: env.block.chain_id,
     };
 
-    return env;
+    env
 }
 
 #[test]
@@ -68,22 +97,32 @@ fn proper_initialization() {
     let mut deps = mock_dependencies();
 
     let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
         recipient_address: "osmo123".to_string(),
-        executor_address: Some(EXECUTOR_ADDR.to_string()),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
         strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
         destinations: vec![CoinWeight {
             denom: "uion".to_string(),
             weight: Uint128::from(100u128),
+            max_price: None,
         }],
         max_slippage: Decimal::from_ratio(1u128, 100u128),
         twap_window_seconds: 1,
         amount_per_trade: Uint128::from(10u128),
         num_trades: Uint128::from(10u128),
-        swap_interval: Duration::Height(100_000_000_000),
+        schedule: Schedule::Interval(Duration::Height(100_000_000_000)),
+        start_at: None,
+        end_at: None,
         source_denom: "uosmo".to_string(),
         router_contract: "osmoabc".to_string(),
         platform_fee: Uint128::zero(),
         platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
     };
 
     let info = mock_info("creator", &coins(100, "uosmo"));
@@ -97,20 +136,30 @@ fn should_fail_because_wrong_denom() {
     let mut deps = mock_dependencies();
 
     let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
         recipient_address: "osmo123".to_string(),
-        executor_address: Some(EXECUTOR_ADDR.to_string()),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
         strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
         platform_fee: Uint128::zero(),
         platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
         destinations: vec![CoinWeight {
             denom: "43Denom".to_string(),
             weight: Uint128::from(100u128),
+            max_price: None,
         }],
         max_slippage: Decimal::from_ratio(1u128, 100u128),
         twap_window_seconds: 1,
         amount_per_trade: Uint128::from(10u128),
         num_trades: Uint128::from(10u128),
-        swap_interval: Duration::Height(100_000_000_000),
+        schedule: Schedule::Interval(Duration::Height(100_000_000_000)),
+        start_at: None,
+        end_at: None,
         source_denom: "uosmo".to_string(),
         router_contract: "osmoabc".to_string(),
     };
@@ -126,13 +175,20 @@ fn dont_init_with_too_many_destinations() {
     let mut deps = mock_dependencies();
 
     let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
         recipient_address: "osmo123".to_string(),
-        executor_address: Some(EXECUTOR_ADDR.to_string()),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
         strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
         destinations: vec![
             CoinWeight {
                 denom: "uion".to_string(),
                 weight: Uint128::from(100u128),
+                max_price: None,
             };
             26
         ],
@@ -140,11 +196,14 @@ fn dont_init_with_too_many_destinations() {
         twap_window_seconds: 1,
         amount_per_trade: Uint128::from(10u128),
         num_trades: Uint128::from(10u128),
-        swap_interval: Duration::Height(100_000_000_000),
+        schedule: Schedule::Interval(Duration::Height(100_000_000_000)),
+        start_at: None,
+        end_at: None,
         source_denom: "uosmo".to_string(),
         router_contract: "osmoabc".to_string(),
         platform_fee: Uint128::zero(),
         platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
     };
 
     let info = mock_info("creator", &coins(100, "uosmo"));
@@ -163,25 +222,35 @@ fn dont_init_with_bad_funds_with_platform_fee() {
     let mut deps = mock_dependencies();
 
     let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
         recipient_address: "osmo123".to_string(),
-        executor_address: Some(EXECUTOR_ADDR.to_string()),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
         strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
         destinations: vec![
             CoinWeight {
                 denom: "uion".to_string(),
                 weight: Uint128::from(100u128),
+                max_price: None,
             };
-            21
+            5
         ],
         max_slippage: Decimal::from_ratio(1u128, 100u128),
         twap_window_seconds: 1,
         amount_per_trade: Uint128::from(10u128),
         num_trades: Uint128::from(10u128),
-        swap_interval: Duration::Height(100_000_000_000),
+        schedule: Schedule::Interval(Duration::Height(100_000_000_000)),
+        start_at: None,
+        end_at: None,
         source_denom: "uosmo".to_string(),
         router_contract: "osmoabc".to_string(),
         platform_fee: Uint128::one(),
         platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
     };
 
     let info = mock_info("creator", &coins(100, "uosmo"));
@@ -203,37 +272,47 @@ fn proper_initialization_with_platform_fee() {
     let mut deps = mock_dependencies();
 
     let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
         recipient_address: "osmo123".to_string(),
-        executor_address: Option::Some(EXECUTOR_ADDR.to_string()),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
         strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
         destinations: vec![CoinWeight {
             denom: "uion".to_string(),
             weight: Uint128::from(100u128),
+            max_price: None,
         }],
         max_slippage: Decimal::from_ratio(1u128, 100u128),
         amount_per_trade: Uint128::from(10u128),
         num_trades: Uint128::from(10u128),
-        swap_interval: Duration::Height(100_000_000_000),
+        schedule: Schedule::Interval(Duration::Height(100_000_000_000)),
+        start_at: None,
+        end_at: None,
         twap_window_seconds: 1,
         source_denom: "uosmo".to_string(),
         router_contract: "osmoabc".to_string(),
         platform_fee: Uint128::one(),
         platform_fee_recipient: "osmo1234".to_string(),
+        croncat: None,
     };
 
     let info = mock_info("creator", &coins(101, "uosmo"));
 
     let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(1, res.messages.len());
-    match &res.messages.get(0).unwrap().msg {
+    match &res.messages.first().unwrap().msg {
         cosmwasm_std::CosmosMsg::Bank(bank_msg) => match bank_msg {
             cosmwasm_std::BankMsg::Send { to_address, amount } => {
                 assert_eq!("osmo1234", to_address);
                 assert_eq!(1, amount.len());
             }
-            _ => assert!(false, "expected BankMsg::Send in response"),
+            _ => panic!("expected BankMsg::Send in response"),
         },
-        _ => assert!(false, "expected BankMsg in response"),
+        _ => panic!("expected BankMsg in response"),
     }
 }
 
@@ -396,16 +475,6 @@ fn cannot_be_resume_if_is_already_resumed() {
     assert_eq!(err.to_string(), "DCA strategy is not paused");
 }
 
-#[test]
-fn proper_token_string_to_coin() {
-    let token_string = "100uosmo";
-
-    let coin = token_string_to_coin(token_string).unwrap();
-
-    assert_eq!(coin.amount, Uint128::from(100u128));
-    assert_eq!(coin.denom, "uosmo".to_string());
-}
-
 #[test]
 fn query_handler_claim_funds() {
     let mut deps = do_instantiate();
@@ -456,3 +525,2144 @@ fn cannot_perform_dca_if_trade_limit_reached() {
 
     assert_eq!(res.to_string(), "Reached max trade limit");
 }
+
+fn instantiate_with_strategy(
+    strategy_type: StrategyType,
+    num_trades: u128,
+    deposit: u128,
+) -> Result<OwnedDeps<MockStorage, MockApi, MockQuerier>, ContractError> {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
+        recipient_address: "osmo123".to_string(),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
+        strategy_type,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
+        destinations: vec![
+            CoinWeight {
+                denom: "uion".to_string(),
+                weight: Uint128::from(100u128),
+                max_price: None,
+            },
+            CoinWeight {
+                denom: "ujuno".to_string(),
+                weight: Uint128::from(100u128),
+                max_price: None,
+            },
+        ],
+        max_slippage: Decimal::from_ratio(1u128, 100u128),
+        twap_window_seconds: 1,
+        amount_per_trade: Uint128::from(10u128),
+        num_trades: Uint128::from(num_trades),
+        schedule: Schedule::Interval(Duration::Time(1)),
+        start_at: None,
+        end_at: None,
+        source_denom: "uosmo".to_string(),
+        router_contract: "osmoabc".to_string(),
+        platform_fee: Uint128::zero(),
+        platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
+    };
+
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &coins(deposit, "uosmo")),
+        msg,
+    )?;
+
+    Ok(deps)
+}
+
+fn swap_amounts(res: &Response) -> Vec<Uint128> {
+    res.messages
+        .iter()
+        .map(|sub_msg| match &sub_msg.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { funds, .. }) => funds[0].amount,
+            _ => panic!("expected WasmMsg::Execute in response"),
+        })
+        .collect()
+}
+
+#[test]
+fn exponential_strategy_requires_schedule_deposit() {
+    let strategy_type = StrategyType::Exponential {
+        factor: Decimal::from_ratio(2u128, 1u128),
+    };
+
+    // 10 + 20 + 40
+    let err = instantiate_with_strategy(strategy_type.clone(), 3, 30)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Amount deposited does not match exactly expected: <70> != actual: <30>\""
+    );
+
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 70).unwrap();
+
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.num_trades_executed = Uint128::from(2u128);
+            Ok(s)
+        })
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        fast_forward_time(mock_env(), 100),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(20u128), Uint128::from(20u128)]
+    );
+}
+
+#[test]
+fn linear_ramp_strategy_steps_between_amounts() {
+    let strategy_type = StrategyType::LinearRamp {
+        start_amount: Uint128::from(10u128),
+        end_amount: Uint128::from(30u128),
+    };
+
+    // 10 + 20 + 30
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 60).unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        fast_forward_time(mock_env(), 100),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(5u128), Uint128::from(5u128)]
+    );
+
+    // a decreasing ramp is allowed too: 30 + 20 + 10
+    let strategy_type = StrategyType::LinearRamp {
+        start_amount: Uint128::from(30u128),
+        end_amount: Uint128::from(10u128),
+    };
+    instantiate_with_strategy(strategy_type, 3, 60).unwrap();
+}
+
+#[test]
+fn upcoming_swaps_project_every_trade_left() {
+    let strategy_type = StrategyType::Exponential {
+        factor: Decimal::from_ratio(2u128, 1u128),
+    };
+
+    // 10 + 20 + 40 + 80 + 160
+    let mut deps = instantiate_with_strategy(strategy_type, 5, 310).unwrap();
+    let env = mock_env();
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.carry_over = vec![Uint128::from(3u128), Uint128::zero()];
+            Ok(s)
+        })
+        .unwrap();
+
+    let upcoming_swaps = |deps: &OwnedDeps<_, _, _>, start_after, limit| -> UpcomingSwapsResponse {
+        from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetAllUpcomingSwaps { start_after, limit },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    };
+
+    let page = upcoming_swaps(&deps, None, Some(2)).swaps;
+    assert_eq!(
+        page.iter().map(|s| s.trade_index).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    // the next trade also swaps what was carried over
+    assert_eq!(
+        page[0].amounts,
+        vec![Coin::new(8, "uosmo"), Coin::new(5, "uosmo")]
+    );
+    assert_eq!(
+        page[1].amounts,
+        vec![Coin::new(10, "uosmo"), Coin::new(10, "uosmo")]
+    );
+    assert_eq!(
+        page[1].next_swap,
+        Expiration::AtTime(env.block.time.plus_seconds(2))
+    );
+
+    let page = upcoming_swaps(&deps, Some(1), None).swaps;
+    assert_eq!(
+        page.iter().map(|s| s.trade_index).collect::<Vec<_>>(),
+        vec![2, 3, 4]
+    );
+    assert_eq!(
+        page[2].amounts,
+        vec![Coin::new(80, "uosmo"), Coin::new(80, "uosmo")]
+    );
+    assert!(upcoming_swaps(&deps, Some(4), None).swaps.is_empty());
+    // paging past the last trade is an empty page, however far past it
+    assert!(upcoming_swaps(&deps, Some(10), None).swaps.is_empty());
+    assert!(upcoming_swaps(&deps, Some(u64::MAX - 1), None)
+        .swaps
+        .is_empty());
+    assert!(upcoming_swaps(&deps, Some(u64::MAX), None).swaps.is_empty());
+    assert_eq!(
+        get_later_swap(
+            &Schedule::Interval(Duration::Time(1)),
+            Expiration::AtTime(env.block.time),
+            u64::MAX
+        )
+        .unwrap_err(),
+        ContractError::CustomError {
+            val: "Trade is due too far ahead".to_string()
+        }
+    );
+
+    // a cancelled strategy has nothing left to swap
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::CancelDca {},
+    )
+    .unwrap();
+    assert!(upcoming_swaps(&deps, None, None).swaps.is_empty());
+}
+
+#[test]
+fn dont_init_with_zero_amount_trades_in_schedule() {
+    // 10 + 1 + 0
+    let strategy_type = StrategyType::Exponential {
+        factor: Decimal::from_ratio(1u128, 10u128),
+    };
+
+    let err = instantiate_with_strategy(strategy_type, 3, 11)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Trade 2 of the schedule would swap 0 tokens".to_string()
+        }
+    );
+}
+
+// the mock router quotes 2 uion or 1 ujuno for every source token
+fn mock_twap_prices(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>) {
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { msg, .. } => match from_binary(msg).unwrap() {
+            RouterQueryMsg::GetTwapPrice { output_denom, .. } => {
+                let price = match output_denom.as_str() {
+                    "uion" => Decimal::from_ratio(2u128, 1u128),
+                    _ => Decimal::one(),
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&GetTwapPriceResponse { price }).unwrap(),
+                ))
+            }
+            _ => panic!("unexpected router query"),
+        },
+        _ => panic!("unexpected wasm query"),
+    });
+}
+
+#[test]
+fn value_averaging_buys_what_is_missing_to_reach_target() {
+    let strategy_type = StrategyType::ValueAveraging {
+        target_increment_per_period: Uint128::from(20u128),
+    };
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    let env = fast_forward_time(mock_env(), 100);
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(30, "uosmo"));
+
+    // first period, nothing bought yet: the target of 20 is split between both destinations
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(10u128), Uint128::from(10u128)]
+    );
+
+    // second period, target of 20 per destination:
+    // 12uion are worth 6, so 14 more are bought. 25ujuno are worth 25, so that swap is skipped
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.num_trades_executed = Uint128::one();
+            s.swap_status = vec![];
+            s.total_bought = vec![Coin::new(12, "uion"), Coin::new(25, "ujuno")];
+            Ok(s)
+        })
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(14u128)]);
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.swap_status.len(), 1);
+    assert!(!state.swap_status[0].executed);
+
+    // the trade can never spend more than what is left of the deposit
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.swap_status = vec![];
+            Ok(s)
+        })
+        .unwrap();
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(5, "uosmo"));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(5u128)]);
+
+    // nor the input owed to a queued retry
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.swap_status = vec![];
+            Ok(s)
+        })
+        .unwrap();
+    RETRY_QUEUE
+        .save(deps.as_mut().storage, (0, 1), &Coin::new(2, "uosmo"))
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(3u128)]);
+}
+
+#[test]
+fn value_averaging_finishes_trade_when_target_is_reached() {
+    let strategy_type = StrategyType::ValueAveraging {
+        target_increment_per_period: Uint128::from(20u128),
+    };
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    let env = fast_forward_time(mock_env(), 100);
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(30, "uosmo"));
+
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.total_bought = vec![Coin::new(100, "uion"), Coin::new(100, "ujuno")];
+            Ok(s)
+        })
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert!(state.swap_status.is_empty());
+}
+
+#[test]
+fn skip_destination_above_max_price_and_carry_amount_over() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    // 1 ujuno costs 1uosmo, which is above its ceiling
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.destinations[1].max_price = Some(Decimal::from_ratio(8u128, 10u128));
+            Ok(c)
+        })
+        .unwrap();
+
+    let env = fast_forward_time(mock_env(), 100);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(5u128)]);
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        state.carry_over,
+        vec![Uint128::zero(), Uint128::from(5u128)]
+    );
+    assert_eq!(state.swap_status.len(), 1);
+    assert!(!state.swap_status[0].executed);
+
+    // once the price is below the ceiling the skipped amount is bought with the next trade
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.destinations[1].max_price = Some(Decimal::from_ratio(3u128, 2u128));
+            Ok(c)
+        })
+        .unwrap();
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.num_trades_executed = Uint128::one();
+            s.swap_status = vec![];
+            Ok(s)
+        })
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(5u128), Uint128::from(10u128)]
+    );
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.carry_over, vec![Uint128::zero(), Uint128::zero()]);
+}
+
+#[test]
+fn atomic_trade_rolls_over_when_a_destination_is_above_max_price() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    // 1 ujuno costs 1uosmo, which is above its ceiling. uion could be bought
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.execution_mode = ExecutionMode::Atomic;
+            c.destinations[1].max_price = Some(Decimal::from_ratio(8u128, 10u128));
+            Ok(c)
+        })
+        .unwrap();
+
+    let env = fast_forward_time(mock_env(), 100);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert!(swap_amounts(&res).is_empty());
+
+    // nothing was swapped, the whole trade is bought with the next one
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert_eq!(
+        state.carry_over,
+        vec![Uint128::from(5u128), Uint128::from(5u128)]
+    );
+}
+
+fn dip_multipliers() -> Vec<DipMultiplier> {
+    vec![
+        DipMultiplier {
+            below_average: Decimal::percent(5),
+            multiplier: Decimal::from_ratio(3u128, 2u128),
+        },
+        DipMultiplier {
+            below_average: Decimal::percent(10),
+            multiplier: Decimal::from_ratio(2u128, 1u128),
+        },
+    ]
+}
+
+#[test]
+fn proper_dip_multiplier() {
+    let multipliers = dip_multipliers();
+    let average = Decimal::one();
+
+    assert_eq!(
+        get_dip_multiplier(&multipliers, Decimal::percent(110), average),
+        Decimal::one()
+    );
+    assert_eq!(
+        get_dip_multiplier(&multipliers, Decimal::percent(97), average),
+        Decimal::one()
+    );
+    assert_eq!(
+        get_dip_multiplier(&multipliers, Decimal::percent(95), average),
+        Decimal::from_ratio(3u128, 2u128)
+    );
+    assert_eq!(
+        get_dip_multiplier(&multipliers, Decimal::percent(50), average),
+        Decimal::from_ratio(2u128, 1u128)
+    );
+    // without an average there is nothing to compare with
+    assert_eq!(
+        get_dip_multiplier(&multipliers, Decimal::percent(50), Decimal::zero()),
+        Decimal::one()
+    );
+}
+
+#[test]
+fn buy_the_dip_scales_trades_and_never_overspends() {
+    let strategy_type = StrategyType::BuyTheDip {
+        multipliers: dip_multipliers(),
+        smoothing: Decimal::percent(50),
+        max_total_spend: None,
+    };
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    // uion is quoted at 0.5uosmo, 50% below its average. ujuno is quoted at its average
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.average_prices = vec![Decimal::one(), Decimal::one()];
+            Ok(s)
+        })
+        .unwrap();
+
+    let env = fast_forward_time(mock_env(), 100);
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(30, "uosmo"));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(10u128), Uint128::from(5u128)]
+    );
+
+    // with only 12 tokens left the trade is cut down to what is still deposited
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.swap_status = vec![];
+            Ok(s)
+        })
+        .unwrap();
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(12, "uosmo"));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(10u128), Uint128::from(2u128)]
+    );
+
+    // the input of a queued retry is owed to it, 4 of the 12 tokens are left to spend
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.swap_status = vec![];
+            Ok(s)
+        })
+        .unwrap();
+    RETRY_QUEUE
+        .save(deps.as_mut().storage, (0, 1), &Coin::new(8, "uosmo"))
+        .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(4u128)]);
+}
+
+#[test]
+fn buy_the_dip_never_spends_more_than_its_max_total_spend() {
+    let strategy_type = StrategyType::BuyTheDip {
+        multipliers: dip_multipliers(),
+        smoothing: Decimal::percent(50),
+        max_total_spend: Some(Uint128::from(16u128)),
+    };
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    // uion is quoted at 0.5uosmo, 50% below its average. ujuno is quoted at its average
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.average_prices = vec![Decimal::one(), Decimal::one()];
+            Ok(s)
+        })
+        .unwrap();
+
+    let env = fast_forward_time(mock_env(), 100);
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(30, "uosmo"));
+
+    // 15 tokens fit into the cap
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(10u128), Uint128::from(5u128)]
+    );
+
+    // only the executed swap counts as spent
+    reply(
+        deps.as_mut(),
+        env.clone(),
+        swap_reply(DCA_SWAP_REPLY_FLAG, 0, 0, Coin::new(20, "uion")),
+    )
+    .unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 1)).unwrap();
+    assert_eq!(
+        TOTAL_SPENT.load(deps.as_ref().storage).unwrap(),
+        Uint128::from(10u128)
+    );
+
+    // only 6 tokens are left to spend, although 20 are still deposited
+    let env = fast_forward_time(env, 100);
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(20, "uosmo"));
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(6u128)]);
+}
+
+#[test]
+fn dont_init_with_zero_max_total_spend() {
+    let strategy_type = StrategyType::BuyTheDip {
+        multipliers: dip_multipliers(),
+        smoothing: Decimal::percent(50),
+        max_total_spend: Some(Uint128::zero()),
+    };
+
+    let err = instantiate_with_strategy(strategy_type, 3, 30)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Max total spend must be greater than 0\""
+    );
+}
+
+#[test]
+fn buy_the_dip_updates_moving_average() {
+    let strategy_type = StrategyType::BuyTheDip {
+        multipliers: dip_multipliers(),
+        smoothing: Decimal::percent(50),
+        max_total_spend: None,
+    };
+    let deps = instantiate_with_strategy(strategy_type, 3, 30).unwrap();
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+
+    let swap_status = vec![
+        SwapEvent {
+            executed: true,
+            token_in: Some(Coin::new(10, "uosmo")),
+            effective_token_out: Some(Coin::new(20, "uion")),
+            timestamp_nanos: 0,
+        },
+        SwapEvent {
+            executed: true,
+            token_in: Some(Coin::new(5, "uosmo")),
+            effective_token_out: Some(Coin::new(5, "ujuno")),
+            timestamp_nanos: 0,
+        },
+    ];
+
+    // uion had an average of 1 and was bought at 0.5, ujuno is bought for the first time
+    let average_prices = update_average_prices(
+        &config,
+        &[Decimal::one()],
+        &swap_status,
+        Decimal::percent(50),
+    );
+    assert_eq!(average_prices, vec![Decimal::percent(75), Decimal::one()]);
+}
+
+#[test]
+fn dont_init_with_too_large_dip_multiplier() {
+    let strategy_type = StrategyType::BuyTheDip {
+        multipliers: vec![DipMultiplier {
+            below_average: Decimal::percent(10),
+            multiplier: Decimal::from_ratio(11u128, 1u128),
+        }],
+        smoothing: Decimal::percent(50),
+        max_total_spend: None,
+    };
+
+    let err = instantiate_with_strategy(strategy_type, 3, 30)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Dip multipliers must be between 0 and 10\""
+    );
+}
+
+// config and state as they were stored by a 0.1.0 deployment
+const CONFIG_V0_1_0: &str = r#"{"owner":"creator","recipient_address":"osmo123","executor_address":"executor","strategy_type":"linear","amount_per_trade":"10","num_trades":"3","swap_interval":{"time":1},"source_denom":"uosmo","destinations":[{"denom":"uion","weight":"100"}],"max_slippage":"0.01","twap_window_seconds":1,"router_contract":"osmoabc"}"#;
+const STATE_V0_1_0: &str = r#"{"next_swap":{"at_time":"1571797420000000000"},"paused":true,"num_trades_executed":"1","swap_status":[]}"#;
+
+fn mock_v0_1_0_storage(
+    contract: &str,
+    version: &str,
+) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, contract, version).unwrap();
+    deps.storage.set(b"config", CONFIG_V0_1_0.as_bytes());
+    deps.storage.set(b"state", STATE_V0_1_0.as_bytes());
+    deps
+}
+
+#[test]
+fn proper_migration_from_v0_1_0() {
+    let mut deps = mock_v0_1_0_storage("crates.io:phase-finance", "0.1.0");
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.strategy_type, StrategyType::Linear);
+    assert_eq!(config.amount_per_trade, Uint128::from(10u128));
+    assert_eq!(
+        config.destinations,
+        vec![CoinWeight {
+            denom: "uion".to_string(),
+            weight: Uint128::from(100u128),
+            max_price: None,
+        }]
+    );
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert!(state.paused);
+    assert_eq!(state.num_trades_executed, Uint128::one());
+    assert!(state.total_bought.is_empty());
+
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn dont_migrate_from_other_contract_or_newer_version() {
+    let mut deps = mock_v0_1_0_storage("crates.io:other-contract", "0.1.0");
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CannotMigrate {
+            previous_contract: "crates.io:other-contract".to_string(),
+            previous_version: "0.1.0".to_string(),
+        }
+    );
+
+    let mut deps = mock_v0_1_0_storage("crates.io:phase-finance", "99.0.0");
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot migrate from crates.io:phase-finance 99.0.0"
+    );
+}
+
+fn update_config_msg() -> UpdateConfigMsg {
+    UpdateConfigMsg {
+        max_slippage: None,
+        twap_window_seconds: None,
+        executor_policy: None,
+        recipient_address: None,
+        schedule: None,
+        router_contract: None,
+        destination_weights: None,
+    }
+}
+
+#[test]
+fn proper_update_config() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let env = fast_forward_time(mock_env(), 100);
+
+    let msg = UpdateConfigMsg {
+        max_slippage: Some(Decimal::percent(2)),
+        schedule: Some(Schedule::Interval(Duration::Time(60))),
+        destination_weights: Some(vec![Uint128::from(1u128), Uint128::from(3u128)]),
+        ..update_config_msg()
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(msg),
+    )
+    .unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.max_slippage, Decimal::percent(2));
+    assert_eq!(config.schedule, Schedule::Interval(Duration::Time(60)));
+    assert_eq!(config.destinations[0].weight, Uint128::from(1u128));
+    assert_eq!(config.destinations[1].weight, Uint128::from(3u128));
+
+    // the next swap moved with the new interval
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.next_swap, Duration::Time(60).after(&env.block));
+}
+
+#[test]
+fn dont_update_config_if_unauthorized_or_invalid() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(update_config_msg()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let msg = UpdateConfigMsg {
+        max_slippage: Some(Decimal::percent(20)),
+        ..update_config_msg()
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(msg),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Max slippage must be between 0% and 15%\""
+    );
+
+    let msg = UpdateConfigMsg {
+        destination_weights: Some(vec![Uint128::one()]),
+        ..update_config_msg()
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(msg),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Expected 2 destination weights, got 1\""
+    );
+}
+
+#[test]
+fn proper_deposit_extends_schedule() {
+    let strategy_type = StrategyType::Exponential {
+        factor: Decimal::from_ratio(2u128, 1u128),
+    };
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 70).unwrap();
+
+    // trades 3 and 4 swap 80 and 160
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &coins(80, "uosmo")),
+        ExecuteMsg::Deposit {
+            extra_trades: Uint128::from(2u128),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Amount deposited does not match exactly expected: <240> != actual: <80>\""
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &coins(240, "uosmo")),
+        ExecuteMsg::Deposit {
+            extra_trades: Uint128::from(2u128),
+        },
+    )
+    .unwrap();
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.num_trades, Uint128::from(5u128));
+}
+
+#[test]
+fn dont_deposit_into_linear_ramp() {
+    let strategy_type = StrategyType::LinearRamp {
+        start_amount: Uint128::from(10u128),
+        end_amount: Uint128::from(30u128),
+    };
+    let mut deps = instantiate_with_strategy(strategy_type, 3, 60).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &coins(30, "uosmo")),
+        ExecuteMsg::Deposit {
+            extra_trades: Uint128::one(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Linear ramp strategies cannot be extended\""
+    );
+}
+
+#[test]
+fn proper_withdraw_of_remaining_trades() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let env = mock_env();
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(30, "uosmo"));
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::Withdraw {
+            trades: Uint128::from(2u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADMIN_ADDR.to_string(),
+            amount: coins(20, "uosmo"),
+        })
+    );
+
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.num_trades, Uint128::one());
+}
+
+#[test]
+fn dont_withdraw_executed_or_in_flight_trades() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let env = mock_env();
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(20, "uosmo"));
+
+    // one trade done and the next one has swaps in flight
+    STATE
+        .update(deps.as_mut().storage, |mut s| -> StdResult<_> {
+            s.num_trades_executed = Uint128::one();
+            s.swap_status = vec![SwapEvent {
+                executed: false,
+                token_in: Some(Coin::new(5, "uosmo")),
+                effective_token_out: None,
+                timestamp_nanos: 0,
+            }];
+            Ok(s)
+        })
+        .unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::Withdraw {
+            trades: Uint128::from(2u128),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Custom Error val: \"Cannot withdraw more than the 1 trades left\""
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::Withdraw {
+            trades: Uint128::one(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn cancel_settles_funds_and_ends_strategy() {
+    let mut deps = do_instantiate();
+    let env = mock_env();
+
+    deps.querier.update_balance(
+        env.contract.address.clone(),
+        vec![Coin::new(60, "uosmo"), Coin::new(25, "uion")],
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::CancelDca {},
+    )
+    .unwrap();
+
+    // unspent funds go back to the owner, bought tokens to the recipient
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|sub_msg| sub_msg.msg.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADMIN_ADDR.to_string(),
+                amount: coins(60, "uosmo"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "osmo123".to_string(),
+                amount: coins(25, "uion"),
+            }),
+        ]
+    );
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("refunded", "60uosmo")));
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("settled", "25uion")));
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.status, DcaStatus::Cancelled);
+
+    let err = execute(
+        deps.as_mut(),
+        fast_forward_time(env.clone(), 100),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DcaCancelled);
+
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::CancelDca {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DcaCancelled);
+}
+
+#[test]
+fn strategy_completes_when_no_trades_are_left() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let env = mock_env();
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(30, "uosmo"));
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::Withdraw {
+            trades: Uint128::from(3u128),
+        },
+    )
+    .unwrap();
+
+    let state = STATE.load(deps.as_ref().storage).unwrap();
+    assert_eq!(state.status, DcaStatus::Completed);
+
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info(ADMIN_ADDR, &coins(10, "uosmo")),
+        ExecuteMsg::Deposit {
+            extra_trades: Uint128::one(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::DcaCompleted);
+}
+
+fn failed_swap_reply(trade_index: u128, destination_index: usize) -> Reply {
+    Reply {
+        id: swap_reply_id(
+            DCA_SWAP_REPLY_FLAG,
+            Uint128::from(trade_index),
+            destination_index,
+        )
+        .unwrap(),
+        result: SubMsgResult::Err("swap failed".to_string()),
+    }
+}
+
+#[test]
+fn swap_history_records_every_destination() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    mock_twap_prices(&mut deps);
+
+    // ujuno is skipped because of its price, uion is swapped but the swap fails
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.destinations[1].max_price = Some(Decimal::percent(80));
+            Ok(c)
+        })
+        .unwrap();
+
+    let env = fast_forward_time(mock_env(), 100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 0)).unwrap();
+
+    // both swaps of the second trade fail
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.destinations[1].max_price = None;
+            Ok(c)
+        })
+        .unwrap();
+    let env = fast_forward_time(env, 100);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.iter().map(|m| m.id).collect::<Vec<_>>(),
+        vec![
+            swap_reply_id(DCA_SWAP_REPLY_FLAG, Uint128::one(), 0).unwrap(),
+            swap_reply_id(DCA_SWAP_REPLY_FLAG, Uint128::one(), 1).unwrap()
+        ]
+    );
+    // every reply is credited to its own destination, whatever order they come back in
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(1, 1)).unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(1, 0)).unwrap();
+
+    let history: SwapHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SwapHistory {
+                start_after: None,
+                limit: None,
+                destination: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        history
+            .swaps
+            .iter()
+            .map(|s| (s.trade_index, s.destination_index))
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (0, 1), (1, 0), (1, 1)]
+    );
+    // the skipped swap still knows what it would have spent
+    assert_eq!(
+        history.swaps[1].swap_event.token_in,
+        Some(Coin::new(5, "uosmo"))
+    );
+
+    let history: SwapHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SwapHistory {
+                start_after: Some((0, 1)),
+                limit: Some(1),
+                destination: Some("ujuno".to_string()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(history.swaps.len(), 1);
+    assert_eq!(history.swaps[0].trade_index, 1);
+    assert_eq!(history.swaps[0].destination_denom, "ujuno");
+
+    // a cursor on another destination of the same trade still includes that trade
+    let history: SwapHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SwapHistory {
+                start_after: Some((0, 0)),
+                limit: None,
+                destination: Some("ujuno".to_string()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        history
+            .swaps
+            .iter()
+            .map(|s| (s.trade_index, s.destination_index))
+            .collect::<Vec<_>>(),
+        vec![(0, 1), (1, 1)]
+    );
+
+    // a denom that isn't bought has no history
+    let history: SwapHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::SwapHistory {
+                start_after: None,
+                limit: None,
+                destination: Some("uatom".to_string()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(history.swaps.is_empty());
+}
+
+#[test]
+fn proper_swap_reply_id() {
+    let id = swap_reply_id(DCA_SWAP_REPLY_FLAG, Uint128::from(300u128), 24).unwrap();
+    assert_eq!(
+        parse_swap_reply_id(DCA_SWAP_REPLY_FLAG, id),
+        Some((300, 24))
+    );
+    // retries of the same swap are told apart by their flag
+    let retry_id = swap_reply_id(DCA_RETRY_REPLY_FLAG, Uint128::from(300u128), 24).unwrap();
+    assert_ne!(id, retry_id);
+    assert_eq!(parse_swap_reply_id(DCA_SWAP_REPLY_FLAG, retry_id), None);
+    assert_eq!(
+        parse_swap_reply_id(DCA_RETRY_REPLY_FLAG, retry_id),
+        Some((300, 24))
+    );
+
+    // ids of other submessages are not swap replies
+    assert_eq!(parse_swap_reply_id(DCA_SWAP_REPLY_FLAG, 0x8008136), None);
+}
+
+#[test]
+fn dont_credit_reply_to_another_trade() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let env = fast_forward_time(mock_env(), 100);
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+
+    let err = reply(deps.as_mut(), env.clone(), failed_swap_reply(1, 0)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Swap reply for trade 1 arrived during trade 0"
+    );
+
+    // a destination can only be credited once
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 0)).unwrap();
+    let err = reply(deps.as_mut(), env, failed_swap_reply(0, 0)).unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: unexpected swap reply");
+}
+
+/// a reply carrying the router's SwapResponse, wrapped like the chain wraps execute responses
+fn swap_reply(flag: u64, trade_index: u128, destination_index: usize, token_out: Coin) -> Reply {
+    let response = to_binary(&swaprouter::msg::SwapResponse {
+        original_sender: "osmoabc".to_string(),
+        token_out_denom: token_out.denom,
+        amount: token_out.amount,
+    })
+    .unwrap();
+    let mut data = vec![0x0a, response.len() as u8];
+    data.extend_from_slice(response.as_slice());
+
+    Reply {
+        id: swap_reply_id(flag, Uint128::from(trade_index), destination_index).unwrap(),
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(data.into()),
+        }),
+    }
+}
+
+fn set_failure_policy(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    failure_policy: FailurePolicy,
+) {
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.failure_policy = failure_policy;
+            Ok(c)
+        })
+        .unwrap();
+}
+
+#[test]
+fn failed_swap_rolls_over_to_next_trade() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    assert_eq!(
+        CONFIG.load(&deps.storage).unwrap().failure_policy,
+        FailurePolicy::RollOver
+    );
+
+    let env = fast_forward_time(mock_env(), 100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    reply(
+        deps.as_mut(),
+        env.clone(),
+        swap_reply(DCA_SWAP_REPLY_FLAG, 0, 0, Coin::new(5, "uion")),
+    )
+    .unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 1)).unwrap();
+
+    // the failed ujuno swap is bought together with the next trade
+    let env = fast_forward_time(env, 100);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(
+        swap_amounts(&res),
+        vec![Uint128::from(5u128), Uint128::from(10u128)]
+    );
+}
+
+#[test]
+fn failed_swap_refunds_owner() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    set_failure_policy(&mut deps, FailurePolicy::Refund);
+
+    let env = fast_forward_time(mock_env(), 100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    let res = reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 1)).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADMIN_ADDR.to_string(),
+            amount: coins(5, "uosmo"),
+        })
+    );
+
+    // nothing is carried over, the next trade is back to its scheduled size
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 0)).unwrap();
+    let res = execute(
+        deps.as_mut(),
+        fast_forward_time(env, 100),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(5u128); 2]);
+}
+
+#[test]
+fn failed_swaps_are_queued_until_retried() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    set_failure_policy(&mut deps, FailurePolicy::RetryQueue);
+
+    let env = fast_forward_time(mock_env(), 100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 0)).unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 1)).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::RetryFailed {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::RetryFailed {},
+    )
+    .unwrap();
+    assert_eq!(swap_amounts(&res), vec![Uint128::from(5u128); 2]);
+    assert_eq!(
+        res.messages[1].id,
+        swap_reply_id(DCA_RETRY_REPLY_FLAG, Uint128::zero(), 1).unwrap()
+    );
+
+    // a retry that fails again stays queued, one that succeeds is sent to the recipient
+    reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: swap_reply_id(DCA_RETRY_REPLY_FLAG, Uint128::zero(), 0).unwrap(),
+            result: SubMsgResult::Err("swap failed".to_string()),
+        },
+    )
+    .unwrap();
+    let res = reply(
+        deps.as_mut(),
+        env.clone(),
+        swap_reply(DCA_RETRY_REPLY_FLAG, 0, 1, Coin::new(5, "ujuno")),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "osmo123".to_string(),
+            amount: coins(5, "ujuno"),
+        })
+    );
+
+    assert_eq!(
+        RETRY_QUEUE
+            .keys(&deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap(),
+        vec![(0, 0)]
+    );
+    let state = STATE.load(&deps.storage).unwrap();
+    assert_eq!(state.total_bought, coins(5, "ujuno"));
+
+    let history: SwapHistoryResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SwapHistory {
+                start_after: None,
+                limit: None,
+                destination: Some("ujuno".to_string()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(history.swaps[0].swap_event.executed);
+
+    // once the queue is cleared there is nothing left to retry
+    reply(
+        deps.as_mut(),
+        env.clone(),
+        swap_reply(DCA_RETRY_REPLY_FLAG, 0, 0, Coin::new(5, "uion")),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::RetryFailed {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "No failed swaps to retry".to_string()
+        }
+    );
+}
+
+#[test]
+fn destination_funds_spend_the_whole_trade() {
+    let deps = instantiate_with_strategy(StrategyType::Linear, 1, 10).unwrap();
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+    config.destinations[0].weight = Uint128::from(1u128);
+    config.destinations[1].weight = Uint128::from(2u128);
+    config.destinations.push(CoinWeight {
+        denom: "uatom".to_string(),
+        weight: Uint128::from(4u128),
+        max_price: None,
+    });
+
+    // 10 * 1/7 = 1.43, 10 * 2/7 = 2.86 and 10 * 4/7 = 5.71 round down to 8 in total, the 2 units
+    // lost to rounding go to the largest remainders of ujuno and uatom
+    let amounts: Vec<Uint128> = get_destination_funds(&config, Uint128::from(10u128))
+        .into_iter()
+        .map(|c| c.amount)
+        .collect();
+    assert_eq!(
+        amounts,
+        vec![
+            Uint128::from(1u128),
+            Uint128::from(3u128),
+            Uint128::from(6u128)
+        ]
+    );
+
+    // equal remainders go to the destination listed first
+    config.destinations[2].weight = Uint128::from(1u128);
+    config.destinations[1].weight = Uint128::from(1u128);
+    let amounts: Vec<Uint128> = get_destination_funds(&config, Uint128::from(10u128))
+        .into_iter()
+        .map(|c| c.amount)
+        .collect();
+    assert_eq!(
+        amounts,
+        vec![
+            Uint128::from(4u128),
+            Uint128::from(3u128),
+            Uint128::from(3u128)
+        ]
+    );
+}
+
+#[test]
+fn dont_init_with_destination_share_rounding_to_zero() {
+    // a linear ramp down to 1 token can't be split between two destinations
+    let strategy_type = StrategyType::LinearRamp {
+        start_amount: Uint128::from(3u128),
+        end_amount: Uint128::one(),
+    };
+
+    let err = instantiate_with_strategy(strategy_type, 3, 6)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Share of uion in a trade of 1 rounds to 0".to_string()
+        }
+    );
+
+    // weights are checked again when they are updated
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            destination_weights: Some(vec![Uint128::from(100u128), Uint128::one()]),
+            ..update_config_msg()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Share of ujuno in a trade of 10 rounds to 0".to_string()
+        }
+    );
+}
+
+#[test]
+fn dont_init_or_update_with_zero_weights() {
+    let mut deps = mock_dependencies();
+    let destination = |denom: &str| CoinWeight {
+        denom: denom.to_string(),
+        weight: Uint128::zero(),
+        max_price: None,
+    };
+    let msg = InstantiateMsg {
+        owner: None,
+        factory: None,
+        recipient_address: "osmo123".to_string(),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
+        strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
+        destinations: vec![destination("uion"), destination("ujuno")],
+        max_slippage: Decimal::from_ratio(1u128, 100u128),
+        twap_window_seconds: 1,
+        amount_per_trade: Uint128::from(10u128),
+        num_trades: Uint128::from(3u128),
+        schedule: Schedule::Interval(Duration::Time(1)),
+        start_at: None,
+        end_at: None,
+        source_denom: "uosmo".to_string(),
+        router_contract: "osmoabc".to_string(),
+        platform_fee: Uint128::zero(),
+        platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
+    };
+
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &coins(30, "uosmo")),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Weight of uion must be greater than 0".to_string()
+        }
+    );
+
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            destination_weights: Some(vec![Uint128::from(100u128), Uint128::zero()]),
+            ..update_config_msg()
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Weight of ujuno must be greater than 0".to_string()
+        }
+    );
+}
+
+#[test]
+fn completed_strategy_sweeps_unspent_funds() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 1, 10).unwrap();
+    mock_twap_prices(&mut deps);
+    CONFIG
+        .update(deps.as_mut().storage, |mut c| -> StdResult<_> {
+            c.destinations[1].max_price = Some(Decimal::percent(80));
+            Ok(c)
+        })
+        .unwrap();
+
+    // the ujuno share of the last trade is skipped and stays in the contract
+    let env = fast_forward_time(mock_env(), 100);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    deps.querier
+        .update_balance(env.contract.address.clone(), coins(5, "uosmo"));
+    let res = reply(
+        deps.as_mut(),
+        env,
+        swap_reply(DCA_SWAP_REPLY_FLAG, 0, 0, Coin::new(5, "uion")),
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages
+            .iter()
+            .map(|m| m.msg.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "osmo123".to_string(),
+                amount: coins(5, "uion"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADMIN_ADDR.to_string(),
+                amount: coins(5, "uosmo"),
+            })
+        ]
+    );
+    let state = STATE.load(&deps.storage).unwrap();
+    assert_eq!(state.status, DcaStatus::Completed);
+    assert!(state.carry_over.is_empty());
+}
+
+#[test]
+fn executor_tips_are_part_of_the_deposit() {
+    let deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+
+    // 10% of every 10 token trade
+    config.executor_tip = Some(ExecutorTip::Bps { bps: 1_000 });
+    validate_dca_config(&config).unwrap();
+    assert_eq!(get_total_deposit(&config).unwrap(), Uint128::from(33u128));
+
+    config.executor_tip = Some(ExecutorTip::Bps { bps: 5 });
+    assert_eq!(
+        validate_dca_config(&config).unwrap_err(),
+        ContractError::CustomError {
+            val: "Executor tip must be greater than 0".to_string()
+        }
+    );
+
+    config.executor_tip = Some(ExecutorTip::Bps { bps: 1_001 });
+    assert_eq!(
+        validate_dca_config(&config).unwrap_err(),
+        ContractError::CustomError {
+            val: "Executor tip must be at most 1000 bps".to_string()
+        }
+    );
+
+    // the trades of other strategies aren't sized by the amount per trade
+    config.executor_tip = Some(ExecutorTip::Bps { bps: 1_000 });
+    config.strategy_type = StrategyType::LinearRamp {
+        start_amount: Uint128::from(5u128),
+        end_amount: Uint128::from(15u128),
+    };
+    assert_eq!(
+        validate_dca_config(&config).unwrap_err(),
+        ContractError::CustomError {
+            val: "Executor tips in bps are only supported by linear strategies".to_string()
+        }
+    );
+    config.executor_tip = Some(ExecutorTip::Fixed {
+        amount: Uint128::one(),
+    });
+    validate_dca_config(&config).unwrap();
+}
+
+#[test]
+fn executor_policy_decides_who_performs_trades() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let env = fast_forward_time(mock_env(), 100);
+
+    let update_policy = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                         executor_policy: ExecutorPolicy| {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(ADMIN_ADDR, &[]),
+            ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+                executor_policy: Some(executor_policy),
+                ..update_config_msg()
+            }),
+        )
+    };
+
+    let err = update_policy(&mut deps, ExecutorPolicy::Allowlist(vec![])).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::CustomError {
+            val: "Executor allowlist must not be empty".to_string()
+        }
+    );
+
+    update_policy(
+        &mut deps,
+        ExecutorPolicy::Allowlist(vec![
+            Addr::unchecked(EXECUTOR_ADDR),
+            Addr::unchecked("keeper"),
+        ]),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 0)).unwrap();
+    reply(deps.as_mut(), env.clone(), failed_swap_reply(0, 1)).unwrap();
+
+    // with anyone allowed, only the timing guards the trade
+    update_policy(&mut deps, ExecutorPolicy::Anyone).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::DcaSwapNotAllowedYet { .. }));
+    execute(
+        deps.as_mut(),
+        fast_forward_time(env, 100),
+        mock_info("anyone", &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+}
+
+#[test]
+fn proper_croncat_interval() {
+    let cron = |time| match get_croncat_interval(&Schedule::Interval(Duration::Time(time))).unwrap()
+    {
+        Interval::Cron(cron) => cron,
+        interval => panic!("unexpected interval {interval:?}"),
+    };
+    assert_eq!(cron(30), "*/30 * * * * *");
+    assert_eq!(cron(300), "0 */5 * * * *");
+    assert_eq!(cron(7_200), "0 0 */2 * * *");
+    assert_eq!(cron(86_400), "0 0 0 * * *");
+    assert_eq!(
+        get_croncat_interval(&Schedule::Interval(Duration::Height(5))).unwrap(),
+        Interval::Block(5)
+    );
+
+    // 90 seconds is no step of a cron field
+    assert_eq!(
+        get_croncat_interval(&Schedule::Interval(Duration::Time(90))).unwrap_err(),
+        ContractError::CustomError {
+            val: "Swap interval can't be scheduled with croncat".to_string()
+        }
+    );
+}
+
+#[test]
+fn croncat_boundary_follows_the_strategy_window() {
+    let deps = do_instantiate();
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+
+    assert_eq!(get_croncat_boundary(&config), None);
+
+    config.start_at = Some(Expiration::AtTime(Timestamp::from_seconds(100)));
+    config.end_at = Some(Expiration::AtTime(Timestamp::from_seconds(200)));
+    assert_eq!(
+        get_croncat_boundary(&config),
+        Some(Boundary::Time {
+            start: Some(100_000_000_000),
+            end: Some(200_000_000_000),
+        })
+    );
+
+    config.schedule = Schedule::Interval(Duration::Height(5));
+    config.start_at = None;
+    config.end_at = Some(Expiration::AtHeight(500));
+    assert_eq!(
+        get_croncat_boundary(&config),
+        Some(Boundary::Height {
+            start: None,
+            end: Some(500),
+        })
+    );
+}
+
+#[test]
+fn cron_schedule_next_fire_times() {
+    // saturday 2022-01-01 00:00:00 UTC
+    let new_year = 1_640_995_200;
+    let next = |cron: &str, after| CronSchedule::from_str(cron).unwrap().next_after(after);
+
+    assert_eq!(next("*/15 * * * * *", new_year), Some(new_year + 15));
+    // monday 2022-01-03 14:00
+    assert_eq!(next("0 0 14 * * MON", new_year), Some(1_641_218_400));
+    assert_eq!(
+        next("0 0 14 * * 1", new_year),
+        next("0 0 14 * * MON", new_year)
+    );
+    // a schedule due right now fires again on its next match
+    assert_eq!(next("0 0 14 * * MON", 1_641_218_400), Some(1_641_823_200));
+    // 2022-02-01, from the 15th of january
+    assert_eq!(next("0 0 0 1 * *", 1_642_204_800), Some(1_643_673_600));
+    // the next 29th of february is in 2024
+    assert_eq!(next("0 0 0 29 FEB *", new_year), Some(1_709_164_800));
+    // when both day fields are restricted either of them matches, friday 2022-01-07 comes first
+    assert_eq!(next("0 0 0 13 * FRI", new_year), Some(1_641_513_600));
+    // sunday 2022-01-02 is 0 or 7
+    assert_eq!(next("0 0 0 * * 7", new_year), Some(new_year + 86_400));
+
+    let err = |cron: &str| CronSchedule::from_str(cron).unwrap_err().to_string();
+    assert_eq!(
+        err("0 0 * * *"),
+        "Custom Error val: \"Invalid cron \\\"0 0 * * *\\\": it must have 6 fields\""
+    );
+    assert_eq!(
+        err("60 * * * * *"),
+        "Custom Error val: \"Invalid cron \\\"60\\\": 60 is not between 0 and 59\""
+    );
+    assert_eq!(
+        err("0 0 0 30 2 *"),
+        "Custom Error val: \"Invalid cron \\\"0 0 0 30 2 *\\\": it never fires\""
+    );
+    assert_eq!(
+        err("*/0 * * * * *"),
+        "Custom Error val: \"Invalid cron \\\"*/0\\\": steps must be greater than 0\""
+    );
+}
+
+#[test]
+fn cron_schedule_decides_when_trades_are_due() {
+    let mut deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    // wednesday 2019-10-23 02:23:39 UTC
+    let env = mock_env();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            schedule: Some(Schedule::Cron("0 0 14 30 2 *".to_string())),
+            ..update_config_msg()
+        }),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("it never fires"));
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(ADMIN_ADDR, &[]),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            schedule: Some(Schedule::Cron("0 0 14 * * MON".to_string())),
+            ..update_config_msg()
+        }),
+    )
+    .unwrap();
+
+    // monday 2019-10-28 14:00, then every week
+    let monday = Timestamp::from_seconds(1_572_271_200);
+    assert_eq!(
+        STATE.load(deps.as_ref().storage).unwrap().next_swap,
+        Expiration::AtTime(monday)
+    );
+    let upcoming: UpcomingSwapsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetAllUpcomingSwaps {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        upcoming
+            .swaps
+            .iter()
+            .map(|s| s.next_swap)
+            .collect::<Vec<_>>(),
+        (0..3)
+            .map(|week| Expiration::AtTime(monday.plus_seconds(week * 7 * 86_400)))
+            .collect::<Vec<_>>()
+    );
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::DcaSwapNotAllowedYet {
+            next_swap: Expiration::AtTime(monday)
+        }
+    );
+
+    let mut env = env;
+    env.block.time = monday;
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(EXECUTOR_ADDR, &[]),
+        ExecuteMsg::PerformDca {},
+    )
+    .unwrap();
+    reply(
+        deps.as_mut(),
+        env.clone(),
+        swap_reply(DCA_SWAP_REPLY_FLAG, 0, 0, Coin::new(5, "uion")),
+    )
+    .unwrap();
+    reply(
+        deps.as_mut(),
+        env,
+        swap_reply(DCA_SWAP_REPLY_FLAG, 0, 1, Coin::new(5, "ujuno")),
+    )
+    .unwrap();
+
+    assert_eq!(
+        STATE.load(deps.as_ref().storage).unwrap().next_swap,
+        Expiration::AtTime(monday.plus_seconds(7 * 86_400))
+    );
+}
+
+#[test]
+fn dont_init_with_bad_start_or_end() {
+    let now = mock_env().block.time;
+    let instantiate_msg = |schedule, start_at, end_at| InstantiateMsg {
+        owner: None,
+        factory: None,
+        recipient_address: "osmo123".to_string(),
+        executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR_ADDR))),
+        strategy_type: StrategyType::Linear,
+        failure_policy: None,
+        execution_mode: None,
+        catch_up_policy: None,
+        executor_tip: None,
+        destinations: vec![CoinWeight {
+            denom: "uion".to_string(),
+            weight: Uint128::from(100u128),
+            max_price: None,
+        }],
+        max_slippage: Decimal::from_ratio(1u128, 100u128),
+        twap_window_seconds: 1,
+        amount_per_trade: Uint128::from(10u128),
+        num_trades: Uint128::from(3u128),
+        schedule,
+        start_at,
+        end_at,
+        source_denom: "uosmo".to_string(),
+        router_contract: "osmoabc".to_string(),
+        platform_fee: Uint128::zero(),
+        platform_fee_recipient: "osmo123".to_string(),
+        croncat: None,
+    };
+    let init = |msg| {
+        instantiate(
+            mock_dependencies().as_mut(),
+            mock_env(),
+            mock_info(ADMIN_ADDR, &coins(30, "uosmo")),
+            msg,
+        )
+    };
+    let custom_error = |val: &str| ContractError::CustomError {
+        val: val.to_string(),
+    };
+    let every_second = Schedule::Interval(Duration::Time(1));
+
+    assert_eq!(
+        init(instantiate_msg(
+            every_second.clone(),
+            Some(Expiration::AtTime(now.minus_seconds(1))),
+            None
+        ))
+        .unwrap_err(),
+        custom_error("Start and end must be in the future")
+    );
+    assert_eq!(
+        init(instantiate_msg(
+            every_second.clone(),
+            None,
+            Some(Expiration::AtHeight(mock_env().block.height + 10))
+        ))
+        .unwrap_err(),
+        custom_error("Start and end must be heights or times like the schedule")
+    );
+    assert_eq!(
+        init(instantiate_msg(
+            every_second,
+            Some(Expiration::AtTime(now.plus_seconds(10))),
+            Some(Expiration::AtTime(now.plus_seconds(10)))
+        ))
+        .unwrap_err(),
+        custom_error("End must be after start")
+    );
+
+    // a cron schedule starts the first time it fires from the start on, monday 2019-10-28 14:00
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDR, &coins(30, "uosmo")),
+        instantiate_msg(
+            Schedule::Cron("0 0 14 * * MON".to_string()),
+            Some(Expiration::AtTime(Timestamp::from_seconds(1_572_271_200))),
+            None,
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        STATE.load(deps.as_ref().storage).unwrap().next_swap,
+        Expiration::AtTime(Timestamp::from_seconds(1_572_271_200))
+    );
+}
+
+#[test]
+fn batched_catch_up_needs_schedule_sized_trades() {
+    let deps = instantiate_with_strategy(StrategyType::Linear, 3, 30).unwrap();
+    let mut config = CONFIG.load(&deps.storage).unwrap();
+
+    config.catch_up_policy = CatchUpPolicy::Batch { max_trades: 3 };
+    validate_dca_config(&config).unwrap();
+
+    config.catch_up_policy = CatchUpPolicy::Batch { max_trades: 0 };
+    assert_eq!(
+        validate_dca_config(&config).unwrap_err(),
+        ContractError::CustomError {
+            val: "Catch up batches must cover at least 1 trade".to_string()
+        }
+    );
+
+    config.catch_up_policy = CatchUpPolicy::Batch { max_trades: 3 };
+    config.strategy_type = StrategyType::ValueAveraging {
+        target_increment_per_period: Uint128::from(10u128),
+    };
+    assert_eq!(
+        validate_dca_config(&config).unwrap_err(),
+        ContractError::CustomError {
+            val: "Missed trades of this strategy type can't be batched".to_string()
+        }
+    );
+
+    // value averaging can still keep its cadence
+    config.catch_up_policy = CatchUpPolicy::Anchor;
+    validate_dca_config(&config).unwrap();
+}
@@ -0,0 +1,135 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Reply, Response, StdError, StdResult,
+};
+
+use cw2::set_contract_version;
+use cw_utils::parse_reply_instantiate_data;
+
+use crate::execute::{try_create_dca, try_update_config, try_update_recipient};
+use crate::query::{
+    query_active_dcas, query_all_dcas, query_config, query_dca, query_dcas_by_owner,
+    query_dcas_by_recipient,
+};
+use crate::state::{CONFIG, DCAS, DCAS_BY_OWNER, DCAS_BY_RECIPIENT, PENDING_DCA};
+
+use phase_finance::constants::INSTANTIATE_DCA_ID;
+use phase_finance::error::ContractError;
+use phase_finance::factory::{DcaRecord, ExecuteMsg, FactoryConfig, InstantiateMsg, QueryMsg};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:phase-finance-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender.clone(),
+    };
+
+    CONFIG.save(
+        deps.storage,
+        &FactoryConfig {
+            owner,
+            dca_code_id: msg.dca_code_id,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("creator", info.sender))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateDca {
+            instantiate_msg,
+            label,
+        } => try_create_dca(deps, env, info, instantiate_msg, label),
+        ExecuteMsg::UpdateConfig { owner, dca_code_id } => {
+            try_update_config(deps, info, owner, dca_code_id)
+        }
+        ExecuteMsg::UpdateRecipient { recipient } => try_update_recipient(deps, info, recipient),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
+    match msg.id {
+        INSTANTIATE_DCA_ID => {
+            let res = parse_reply_instantiate_data(msg)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            let address = deps.api.addr_validate(&res.contract_address)?;
+
+            let (owner, recipient) = PENDING_DCA.load(deps.storage)?;
+            PENDING_DCA.remove(deps.storage);
+
+            DCAS.save(
+                deps.storage,
+                &address,
+                &DcaRecord {
+                    address: address.clone(),
+                    owner: owner.clone(),
+                    recipient: recipient.clone(),
+                    created_at: env.block.time,
+                },
+            )?;
+            DCAS_BY_OWNER.save(deps.storage, (&owner, &address), &Empty {})?;
+            DCAS_BY_RECIPIENT.save(deps.storage, (&recipient, &address), &Empty {})?;
+
+            Ok(Response::new()
+                .add_attribute("method", "instantiate_dca_reply")
+                .add_attribute("dca_address", address)
+                .add_attribute("owner", owner)
+                .add_attribute("recipient", recipient))
+        }
+        _ => Err(StdError::GenericErr {
+            msg: "unknown reply id".to_string(),
+        }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Dca { address } => to_binary(&query_dca(deps, address)?),
+        QueryMsg::DcasByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_dcas_by_owner(deps, owner, start_after, limit)?),
+        QueryMsg::DcasByRecipient {
+            recipient,
+            start_after,
+            limit,
+        } => to_binary(&query_dcas_by_recipient(
+            deps,
+            recipient,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllDcas { start_after, limit } => {
+            to_binary(&query_all_dcas(deps, start_after, limit)?)
+        }
+        QueryMsg::ActiveDcas { start_after, limit } => {
+            to_binary(&query_active_dcas(deps, start_after, limit)?)
+        }
+    }
+}
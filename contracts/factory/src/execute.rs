@@ -0,0 +1,91 @@
+use cosmwasm_std::{
+    ensure_eq, to_binary, DepsMut, Empty, Env, MessageInfo, Response, SubMsg, WasmMsg,
+};
+
+use phase_finance::constants::INSTANTIATE_DCA_ID;
+use phase_finance::error::ContractError;
+use phase_finance::msg::InstantiateMsg as DcaInstantiateMsg;
+
+use crate::state::{CONFIG, DCAS, DCAS_BY_RECIPIENT, PENDING_DCA};
+
+const DEFAULT_LABEL: &str = "phase-finance-dca";
+
+pub fn try_create_dca(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut instantiate_msg: Box<DcaInstantiateMsg>,
+    label: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // the factory instantiates the strategy, so the owner has to be passed explicitly
+    let owner = match &instantiate_msg.owner {
+        Some(owner) => deps.api.addr_validate(owner)?,
+        None => info.sender.clone(),
+    };
+    let recipient = deps.api.addr_validate(&instantiate_msg.recipient_address)?;
+    instantiate_msg.owner = Some(owner.to_string());
+    instantiate_msg.factory = Some(env.contract.address.to_string());
+
+    PENDING_DCA.save(deps.storage, &(owner.clone(), recipient))?;
+
+    let msg = WasmMsg::Instantiate {
+        admin: Some(owner.to_string()),
+        code_id: config.dca_code_id,
+        msg: to_binary(&instantiate_msg)?,
+        funds: info.funds,
+        label: label.unwrap_or_else(|| DEFAULT_LABEL.to_string()),
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(msg, INSTANTIATE_DCA_ID))
+        .add_attribute("method", "try_create_dca")
+        .add_attribute("owner", owner))
+}
+
+pub fn try_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Option<String>,
+    dca_code_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.addr_validate(&owner)?;
+    }
+    if let Some(dca_code_id) = dca_code_id {
+        config.dca_code_id = dca_code_id;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_config")
+        .add_attribute("owner", config.owner)
+        .add_attribute("dca_code_id", config.dca_code_id.to_string()))
+}
+
+pub fn try_update_recipient(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    // only strategies created by this factory report their recipient
+    let mut record = DCAS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::Unauthorized {})?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    DCAS_BY_RECIPIENT.remove(deps.storage, (&record.recipient, &record.address));
+    DCAS_BY_RECIPIENT.save(deps.storage, (&recipient, &record.address), &Empty {})?;
+    record.recipient = recipient;
+    DCAS.save(deps.storage, &record.address, &record)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_recipient")
+        .add_attribute("dca_address", record.address)
+        .add_attribute("recipient", record.recipient))
+}
@@ -0,0 +1,43 @@
+pub use anyhow::Result;
+pub use derivative::Derivative;
+
+pub use crate::contract::{
+    execute as executeFactory, instantiate as instantiateFactory, query as queryFactory,
+    reply as replyFactory,
+};
+pub use cosmwasm_std::{coin, Addr, Coin, Decimal, Empty, StdResult, Uint128};
+pub use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+
+pub use cw_utils::Duration;
+
+pub use pf_dca::contract::{
+    execute as executeDCA, instantiate as instantiateDCA, query as queryDCA, reply as replyDCA,
+};
+
+pub use phase_finance::{
+    error::ContractError as FactoryContractError,
+    factory::{
+        ActiveDcasResponse, DcaRecord, DcasResponse, ExecuteMsg as FactoryExecuteMsg,
+        FactoryConfig, InstantiateMsg as FactoryInstantiateMsg, QueryMsg as FactoryQueryMsg,
+    },
+    msg::{ExecuteMsg as DCAExecuteMsg, InstantiateMsg as DCAInstantiateMsg, UpdateConfigMsg},
+    types::{CoinWeight, DcaConfig, ExecutorPolicy, Schedule, State, StrategyType},
+};
+
+pub const USER: &str = "user";
+pub const RECIPIENT: &str = "recipient";
+pub const DEPLOYER: &str = "deployer";
+pub const EXECUTOR: &str = "executor";
+pub const ROUTER: &str = "router";
+pub const DENOM: &str = "uosmo";
+
+pub fn contract_factory() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(executeFactory, instantiateFactory, queryFactory)
+        .with_reply(replyFactory);
+    Box::new(contract)
+}
+
+pub fn contract_dca() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(executeDCA, instantiateDCA, queryDCA).with_reply(replyDCA);
+    Box::new(contract)
+}
@@ -0,0 +1,254 @@
+use crate::multitest::common::*;
+use crate::multitest::suite::*;
+
+#[test]
+fn create_dca_indexes_owner_and_recipient() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+
+    let dca = suite
+        .create_dca(&user, suite.default_dca_msg(), &[coin(100, DENOM)])
+        .unwrap();
+
+    // the strategy is owned by the caller, not by the factory
+    let config = suite.query_dca_config(&dca).unwrap();
+    assert_eq!(config.owner, user.to_string());
+
+    let record = suite.query_dca(&dca).unwrap();
+    assert_eq!(record.owner, user);
+    assert_eq!(record.recipient, suite.recipient);
+
+    let by_owner = suite.query_dcas_by_owner(&user, None, None).unwrap();
+    assert_eq!(by_owner.dcas, vec![record.clone()]);
+
+    let by_recipient = suite
+        .query_dcas_by_recipient(&suite.recipient.clone(), None, None)
+        .unwrap();
+    assert_eq!(by_recipient.dcas, vec![record]);
+
+    let deployer = suite.deployer.clone();
+    let by_deployer = suite.query_dcas_by_owner(&deployer, None, None).unwrap();
+    assert!(by_deployer.dcas.is_empty());
+
+    // the owner can manage the strategy directly
+    suite.cancel_dca(&user, &dca).unwrap();
+    assert_eq!(
+        suite.app.wrap().query_balance(&user, DENOM).unwrap().amount,
+        Uint128::from(50000u128)
+    );
+}
+
+#[test]
+fn recipient_change_moves_the_dca_in_the_recipient_index() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+    let old_recipient = suite.recipient.clone();
+    let new_recipient = Addr::unchecked("new_recipient");
+
+    let dca = suite
+        .create_dca(&user, suite.default_dca_msg(), &[coin(100, DENOM)])
+        .unwrap();
+
+    suite
+        .app
+        .execute_contract(
+            user.clone(),
+            dca.clone(),
+            &DCAExecuteMsg::UpdateConfig(UpdateConfigMsg {
+                max_slippage: None,
+                twap_window_seconds: None,
+                executor_policy: None,
+                recipient_address: Some(new_recipient.to_string()),
+                schedule: None,
+                router_contract: None,
+                destination_weights: None,
+            }),
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(suite.query_dca(&dca).unwrap().recipient, new_recipient);
+    assert!(suite
+        .query_dcas_by_recipient(&old_recipient, None, None)
+        .unwrap()
+        .dcas
+        .is_empty());
+    assert_eq!(
+        suite
+            .query_dcas_by_recipient(&new_recipient, None, None)
+            .unwrap()
+            .dcas
+            .len(),
+        1
+    );
+
+    // only strategies of the factory can report a recipient
+    let err = suite
+        .app
+        .execute_contract(
+            user,
+            suite.factory.clone(),
+            &FactoryExecuteMsg::UpdateRecipient {
+                recipient: old_recipient.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<FactoryContractError>().unwrap(),
+        FactoryContractError::Unauthorized {}
+    );
+}
+
+#[test]
+fn create_dca_fails_with_wrong_funds() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+
+    let err = suite
+        .create_dca(&user, suite.default_dca_msg(), &[coin(99, DENOM)])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        FactoryContractError::CustomError {
+            val: "Amount deposited does not match exactly expected: <100> != actual: <99>"
+                .to_string()
+        }
+    );
+
+    let all = suite.query_all_dcas(None, None).unwrap();
+    assert!(all.dcas.is_empty());
+}
+
+#[test]
+fn paginate_dcas_by_owner() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+
+    let mut created = vec![];
+    for _ in 0..3 {
+        created.push(
+            suite
+                .create_dca(&user, suite.default_dca_msg(), &[coin(100, DENOM)])
+                .unwrap(),
+        );
+    }
+    created.sort();
+
+    let first_page = suite.query_dcas_by_owner(&user, None, Some(2)).unwrap();
+    assert_eq!(
+        first_page
+            .dcas
+            .iter()
+            .map(|dca| dca.address.clone())
+            .collect::<Vec<_>>(),
+        created[..2].to_vec()
+    );
+
+    let second_page = suite
+        .query_dcas_by_owner(&user, Some(created[1].to_string()), Some(2))
+        .unwrap();
+    assert_eq!(second_page.dcas.len(), 1);
+    assert_eq!(second_page.dcas[0].address, created[2]);
+
+    let all = suite.query_all_dcas(None, None).unwrap();
+    assert_eq!(all.dcas.len(), 3);
+}
+
+#[test]
+fn owner_performs_trades_by_default() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+
+    let mut msg = suite.default_dca_msg();
+    msg.executor_policy = None;
+    let dca = suite.create_dca(&user, msg, &[coin(100, DENOM)]).unwrap();
+
+    // the factory sent the instantiate message, but the strategy is executed by its owner
+    let config = suite.query_dca_config(&dca).unwrap();
+    assert_eq!(config.executor_policy, ExecutorPolicy::Single(user.clone()));
+
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(10));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(EXECUTOR),
+            dca.clone(),
+            &DCAExecuteMsg::PerformDca {},
+            &[],
+        )
+        .unwrap_err();
+    suite
+        .app
+        .execute_contract(user, dca.clone(), &DCAExecuteMsg::PerformDca {}, &[])
+        .unwrap();
+
+    let state = suite.query_dca_state(&dca).unwrap();
+    assert_eq!(state.num_trades_executed, Uint128::one());
+}
+
+#[test]
+fn active_dcas_excludes_finished_strategies() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+
+    let active = suite
+        .create_dca(&user, suite.default_dca_msg(), &[coin(100, DENOM)])
+        .unwrap();
+
+    let mut msg = suite.default_dca_msg();
+    msg.num_trades = Uint128::one();
+    let finished = suite.create_dca(&user, msg, &[coin(10, DENOM)]).unwrap();
+
+    // execute the only trade of the second strategy
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(10));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(EXECUTOR),
+            finished.clone(),
+            &DCAExecuteMsg::PerformDca {},
+            &[],
+        )
+        .unwrap();
+
+    let res = suite.query_active_dcas(None, None).unwrap();
+    assert_eq!(
+        res.dcas
+            .iter()
+            .map(|dca| dca.address.clone())
+            .collect::<Vec<_>>(),
+        vec![active.clone()]
+    );
+    assert_eq!(res.next_start_after, None);
+
+    // a full page hands out a cursor to continue scanning from
+    let first_page = suite.query_active_dcas(None, Some(1)).unwrap();
+    assert_eq!(first_page.dcas.len(), 1);
+    assert_eq!(first_page.next_start_after, Some(active.clone()));
+
+    let second_page = suite
+        .query_active_dcas(Some(active.to_string()), Some(1))
+        .unwrap();
+    assert!(second_page.dcas.is_empty());
+    assert_eq!(second_page.next_start_after, None);
+}
+
+#[test]
+fn only_owner_can_update_config() {
+    let mut suite = FactorySuite::init().unwrap();
+    let user = suite.user.clone();
+    let deployer = suite.deployer.clone();
+
+    let err = suite.update_config(&user, None, Some(42)).unwrap_err();
+    assert_eq!(err, FactoryContractError::Unauthorized {});
+
+    suite.update_config(&deployer, None, Some(42)).unwrap();
+    let config = suite.query_config().unwrap();
+    assert_eq!(config.dca_code_id, 42);
+    assert_eq!(config.owner, deployer);
+}
@@ -0,0 +1,3 @@
+pub mod common;
+pub mod factory;
+pub mod suite;
@@ -0,0 +1,207 @@
+use crate::multitest::common::*;
+use cw_multi_test::App;
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct FactorySuite {
+    #[derivative(Debug = "ignore")]
+    pub app: App,
+    // The account that deploys everything
+    pub deployer: Addr,
+    // user address
+    pub user: Addr,
+    // recipient of the bought tokens
+    pub recipient: Addr,
+    // factory address
+    pub factory: Addr,
+    // code id of the pf-dca contract
+    pub dca_code_id: u64,
+}
+
+impl FactorySuite {
+    pub fn init() -> Result<FactorySuite> {
+        let genesis_funds = vec![coin(150000, DENOM)];
+        let deployer = Addr::unchecked(DEPLOYER);
+        let user = Addr::unchecked(USER);
+        let recipient = Addr::unchecked(RECIPIENT);
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &deployer, genesis_funds)
+                .unwrap();
+        });
+        app.send_tokens(deployer.clone(), user.clone(), &[coin(50000, DENOM)])?;
+
+        let dca_code_id = app.store_code(contract_dca());
+        let factory_id = app.store_code(contract_factory());
+
+        let factory = app
+            .instantiate_contract(
+                factory_id,
+                deployer.clone(),
+                &FactoryInstantiateMsg {
+                    owner: None,
+                    dca_code_id,
+                },
+                &[],
+                "factory_contract",
+                Some(deployer.to_string()),
+            )
+            .unwrap();
+
+        Ok(FactorySuite {
+            app,
+            deployer,
+            user,
+            recipient,
+            factory,
+            dca_code_id,
+        })
+    }
+
+    pub fn default_dca_msg(&self) -> DCAInstantiateMsg {
+        DCAInstantiateMsg {
+            owner: None,
+            factory: None,
+            recipient_address: self.recipient.to_string(),
+            executor_policy: Some(ExecutorPolicy::Single(Addr::unchecked(EXECUTOR))),
+            router_contract: ROUTER.to_string(),
+            strategy_type: StrategyType::Linear,
+            failure_policy: None,
+            execution_mode: None,
+            catch_up_policy: None,
+            executor_tip: None,
+            max_slippage: Decimal::from_ratio(1u128, 100u128),
+            twap_window_seconds: 1,
+            num_trades: Uint128::from(10u128),
+            schedule: Schedule::Interval(Duration::Time(1)),
+            start_at: None,
+            end_at: None,
+            source_denom: DENOM.to_string(),
+            amount_per_trade: Uint128::from(10u128),
+            destinations: vec![CoinWeight {
+                denom: "uion".to_string(),
+                weight: Uint128::from(100u128),
+                max_price: None,
+            }],
+            platform_fee: Uint128::zero(),
+            platform_fee_recipient: "osmo123".to_string(),
+            croncat: None,
+        }
+    }
+
+    pub fn create_dca(
+        &mut self,
+        sender: &Addr,
+        instantiate_msg: DCAInstantiateMsg,
+        funds: &[Coin],
+    ) -> Result<Addr, FactoryContractError> {
+        let msg = FactoryExecuteMsg::CreateDca {
+            instantiate_msg: Box::new(instantiate_msg),
+            label: None,
+        };
+        let res = self
+            .app
+            .execute_contract(sender.clone(), self.factory.clone(), &msg, funds)
+            .map_err(|err| err.downcast::<FactoryContractError>().unwrap())?;
+
+        let address = res
+            .events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == "dca_address")
+            .map(|attr| Addr::unchecked(attr.value.clone()))
+            .unwrap();
+        Ok(address)
+    }
+
+    pub fn update_config(
+        &mut self,
+        sender: &Addr,
+        owner: Option<String>,
+        dca_code_id: Option<u64>,
+    ) -> Result<(), FactoryContractError> {
+        let msg = FactoryExecuteMsg::UpdateConfig { owner, dca_code_id };
+        self.app
+            .execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    pub fn cancel_dca(&mut self, sender: &Addr, dca: &Addr) -> Result<AppResponse> {
+        self.app.execute_contract(
+            sender.clone(),
+            dca.clone(),
+            &DCAExecuteMsg::CancelDca {},
+            &[],
+        )
+    }
+
+    pub fn query_config(&self) -> StdResult<FactoryConfig> {
+        let msg = FactoryQueryMsg::Config {};
+        self.app.wrap().query_wasm_smart(self.factory.clone(), &msg)
+    }
+
+    pub fn query_dca(&self, address: &Addr) -> StdResult<DcaRecord> {
+        let msg = FactoryQueryMsg::Dca {
+            address: address.to_string(),
+        };
+        self.app.wrap().query_wasm_smart(self.factory.clone(), &msg)
+    }
+
+    pub fn query_dca_config(&self, address: &Addr) -> StdResult<DcaConfig> {
+        let msg = phase_finance::msg::QueryMsg::Config {};
+        self.app.wrap().query_wasm_smart(address.clone(), &msg)
+    }
+
+    pub fn query_dca_state(&self, address: &Addr) -> StdResult<State> {
+        let msg = phase_finance::msg::QueryMsg::State {};
+        self.app.wrap().query_wasm_smart(address.clone(), &msg)
+    }
+
+    pub fn query_dcas_by_owner(
+        &self,
+        owner: &Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DcasResponse> {
+        let msg = FactoryQueryMsg::DcasByOwner {
+            owner: owner.to_string(),
+            start_after,
+            limit,
+        };
+        self.app.wrap().query_wasm_smart(self.factory.clone(), &msg)
+    }
+
+    pub fn query_dcas_by_recipient(
+        &self,
+        recipient: &Addr,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DcasResponse> {
+        let msg = FactoryQueryMsg::DcasByRecipient {
+            recipient: recipient.to_string(),
+            start_after,
+            limit,
+        };
+        self.app.wrap().query_wasm_smart(self.factory.clone(), &msg)
+    }
+
+    pub fn query_all_dcas(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<DcasResponse> {
+        let msg = FactoryQueryMsg::AllDcas { start_after, limit };
+        self.app.wrap().query_wasm_smart(self.factory.clone(), &msg)
+    }
+
+    pub fn query_active_dcas(
+        &self,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<ActiveDcasResponse> {
+        let msg = FactoryQueryMsg::ActiveDcas { start_after, limit };
+        self.app.wrap().query_wasm_smart(self.factory.clone(), &msg)
+    }
+}
@@ -0,0 +1,147 @@
+use cosmwasm_std::{Addr, Deps, Order, StdResult};
+use cw_storage_plus::Bound;
+
+use phase_finance::constants::{DEFAULT_LIMIT, MAX_LIMIT};
+use phase_finance::factory::{ActiveDcasResponse, DcaRecord, DcasResponse, FactoryConfig};
+use phase_finance::msg::QueryMsg as DcaQueryMsg;
+use phase_finance::types::{DcaStatus, State};
+
+use crate::state::{CONFIG, DCAS, DCAS_BY_OWNER, DCAS_BY_RECIPIENT};
+// every strategy scanned for active dcas costs a query, so a page stops scanning after this many
+const MAX_SCANNED: usize = 100;
+
+pub fn query_config(deps: Deps) -> StdResult<FactoryConfig> {
+    CONFIG.load(deps.storage)
+}
+
+pub fn query_dca(deps: Deps, address: String) -> StdResult<DcaRecord> {
+    let address = deps.api.addr_validate(&address)?;
+    DCAS.load(deps.storage, &address)
+}
+
+pub fn query_dcas_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<DcasResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let start_after = validate_start_after(deps, start_after)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let dcas = DCAS_BY_OWNER
+        .prefix(&owner)
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| DCAS.load(deps.storage, &address?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DcasResponse { dcas })
+}
+
+pub fn query_dcas_by_recipient(
+    deps: Deps,
+    recipient: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<DcasResponse> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let start_after = validate_start_after(deps, start_after)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let dcas = DCAS_BY_RECIPIENT
+        .prefix(&recipient)
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|address| DCAS.load(deps.storage, &address?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DcasResponse { dcas })
+}
+
+pub fn query_all_dcas(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<DcasResponse> {
+    let start_after = validate_start_after(deps, start_after)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let dcas = DCAS
+        .range(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, dca)| dca))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DcasResponse { dcas })
+}
+
+pub fn query_active_dcas(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ActiveDcasResponse> {
+    let start_after = validate_start_after(deps, start_after)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let mut records = DCAS.range(
+        deps.storage,
+        start_after.as_ref().map(Bound::exclusive),
+        None,
+        Order::Ascending,
+    );
+
+    let mut dcas = vec![];
+    let mut last_scanned = None;
+    for item in records.by_ref().take(MAX_SCANNED) {
+        let (address, dca) = item?;
+        if is_active(deps, &address)? {
+            dcas.push(dca);
+        }
+        last_scanned = Some(address);
+        if dcas.len() == limit {
+            break;
+        }
+    }
+
+    // only hand out a cursor when there is something left to scan
+    let next_start_after = match records.next() {
+        Some(_) => last_scanned,
+        None => None,
+    };
+
+    Ok(ActiveDcasResponse {
+        dcas,
+        next_start_after,
+    })
+}
+
+// a strategy is active until all of its trades are executed or it is cancelled
+fn is_active(deps: Deps, address: &Addr) -> StdResult<bool> {
+    let state: State = deps
+        .querier
+        .query_wasm_smart(address, &DcaQueryMsg::State {})?;
+
+    Ok(state.status == DcaStatus::Active)
+}
+
+fn validate_start_after(deps: Deps, start_after: Option<String>) -> StdResult<Option<Addr>> {
+    start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()
+}
@@ -0,0 +1,10 @@
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Item, Map};
+use phase_finance::factory::{DcaRecord, FactoryConfig};
+
+pub const CONFIG: Item<FactoryConfig> = Item::new("config");
+// owner and recipient of the strategy currently being instantiated, read back in the reply
+pub const PENDING_DCA: Item<(Addr, Addr)> = Item::new("pending_dca");
+pub const DCAS: Map<&Addr, DcaRecord> = Map::new("dcas");
+pub const DCAS_BY_OWNER: Map<(&Addr, &Addr), Empty> = Map::new("dcas_by_owner");
+pub const DCAS_BY_RECIPIENT: Map<(&Addr, &Addr), Empty> = Map::new("dcas_by_recipient");
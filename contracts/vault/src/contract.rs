@@ -0,0 +1,172 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{
+    coin, from_binary, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Reply,
+    Response, StdError, StdResult, SubMsgResult, Uint128,
+};
+
+use cw2::set_contract_version;
+use cw_utils::parse_reply_execute_data;
+
+use crate::execute::{
+    pause_position, resume_position, try_cancel_position, try_create_position, try_perform_dca,
+};
+use crate::query::{query_position, query_positions, query_positions_by_owner};
+use crate::state::{POSITIONS, SOURCE_BALANCES};
+
+use pf_dca::helpers::{add_coins, get_destination_funds, get_next_swap, get_trade_amount};
+use phase_finance::error::ContractError;
+use phase_finance::types::{DcaStatus, State, SwapEvent};
+use phase_finance::vault::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use swaprouter::msg::SwapResponse;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:phase-finance-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("creator", info.sender))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreatePosition { position } => try_create_position(deps, env, info, *position),
+        ExecuteMsg::PerformDca { position_ids } => try_perform_dca(deps, env, info, position_ids),
+        ExecuteMsg::PausePosition { position_id } => pause_position(deps, info, position_id),
+        ExecuteMsg::ResumePosition { position_id } => resume_position(deps, env, info, position_id),
+        ExecuteMsg::CancelPosition { position_id } => try_cancel_position(deps, info, position_id),
+    }
+}
+
+/// every swap reply carries the id of the position it was sent for
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> StdResult<Response> {
+    let position_id = msg.id;
+    let (config, mut state) = POSITIONS
+        .may_load(deps.storage, position_id)?
+        .ok_or_else(|| StdError::generic_err("unknown reply id"))?;
+
+    // replies come back in the same order the swaps were sent in
+    let destination_index = state.swap_status.len();
+    let trade_amount = get_trade_amount(&config, state.num_trades_executed)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let in_funds = get_destination_funds(&config, trade_amount)
+        .get(destination_index)
+        .cloned()
+        .ok_or_else(|| StdError::generic_err("unexpected swap reply"))?;
+
+    let swap_event = match msg.result {
+        SubMsgResult::Ok(_) => {
+            let data = parse_reply_execute_data(msg)
+                .map_err(|err| StdError::generic_err(err.to_string()))?
+                .data
+                .ok_or_else(|| StdError::generic_err("missing swap response data"))?;
+            let res: SwapResponse = from_binary(&data)?;
+
+            SwapEvent {
+                executed: true,
+                token_in: Some(in_funds),
+                effective_token_out: Some(coin(res.amount.u128(), res.token_out_denom)),
+                timestamp_nanos: env.block.time.nanos(),
+            }
+        }
+        SubMsgResult::Err(_) => {
+            // the swap didn't happen, so the input is still owned by the position
+            SOURCE_BALANCES.update(deps.storage, position_id, |balance| {
+                balance
+                    .unwrap_or_default()
+                    .checked_add(in_funds.amount)
+                    .map_err(StdError::from)
+            })?;
+
+            SwapEvent {
+                executed: false,
+                token_in: Some(in_funds),
+                effective_token_out: None,
+                timestamp_nanos: env.block.time.nanos(),
+            }
+        }
+    };
+    state.swap_status.push(swap_event);
+
+    // we are still waiting for more responses
+    if state.swap_status.len() < config.destinations.len() {
+        POSITIONS.save(deps.storage, position_id, &(config, state))?;
+        return Ok(Response::new());
+    }
+
+    // all swaps of this trade are done, send the bought coins to the recipient
+    let bought: Vec<Coin> = state
+        .swap_status
+        .iter()
+        .filter_map(|swap_event| swap_event.effective_token_out.clone())
+        .collect();
+
+    let mut total_bought = state.total_bought;
+    add_coins(&mut total_bought, &bought);
+
+    let mut msgs = vec![];
+    if !bought.is_empty() {
+        msgs.push(BankMsg::Send {
+            to_address: config.recipient_address.clone(),
+            amount: bought,
+        });
+    }
+
+    let next_swap = get_next_swap(&config.schedule, &env.block)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let num_trades_executed = state.num_trades_executed + Uint128::one();
+    let status = if num_trades_executed >= config.num_trades {
+        DcaStatus::Completed
+    } else {
+        DcaStatus::Active
+    };
+    let state = State {
+        status,
+        next_swap,
+        paused: state.paused,
+        num_trades_executed,
+        swap_status: vec![],
+        total_bought,
+        carry_over: state.carry_over,
+        average_prices: state.average_prices,
+    };
+    POSITIONS.save(deps.storage, position_id, &(config, state))?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "finish_position_swap")
+        .add_attribute("position_id", position_id.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Position { position_id } => to_binary(&query_position(deps, position_id)?),
+        QueryMsg::PositionsByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_positions_by_owner(deps, owner, start_after, limit)?),
+        QueryMsg::Positions { start_after, limit } => {
+            to_binary(&query_positions(deps, start_after, limit)?)
+        }
+    }
+}
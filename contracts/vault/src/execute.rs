@@ -0,0 +1,240 @@
+use cosmwasm_std::{
+    ensure, ensure_eq, to_binary, BankMsg, Coin, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, SubMsg, Uint128, WasmMsg,
+};
+
+use pf_dca::helpers::{
+    build_dca_config, ensure_active, get_destination_funds, get_next_swap, get_platform_fee_msg,
+    get_total_trade_amount, get_trade_amount, is_executor,
+};
+use phase_finance::error::ContractError;
+use phase_finance::msg::InstantiateMsg as DcaInstantiateMsg;
+use phase_finance::types::{
+    CatchUpPolicy, DcaStatus, ExecutionMode, FailurePolicy, State, StrategyType,
+};
+
+use crate::state::{NEXT_POSITION_ID, POSITIONS, POSITIONS_BY_OWNER, SOURCE_BALANCES};
+
+pub fn try_create_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position: DcaInstantiateMsg,
+) -> Result<Response, ContractError> {
+    let config = build_dca_config(deps.as_ref(), &info, &position)?;
+
+    // trades of a position are sized by its schedule alone, price driven strategies need their own contract.
+    // the input of failed swaps always stays with the position
+    ensure!(
+        !matches!(
+            config.strategy_type,
+            StrategyType::ValueAveraging { .. } | StrategyType::BuyTheDip { .. }
+        ),
+        unsupported_by_vault("Strategy type")
+    );
+    ensure!(
+        config.destinations.iter().all(|d| d.max_price.is_none()),
+        unsupported_by_vault("Destination max price")
+    );
+    ensure!(
+        config.failure_policy == FailurePolicy::RollOver,
+        unsupported_by_vault("Failure policy")
+    );
+    ensure!(
+        config.execution_mode == ExecutionMode::BestEffort,
+        unsupported_by_vault("Execution mode")
+    );
+    ensure!(
+        config.catch_up_policy == CatchUpPolicy::Skip,
+        unsupported_by_vault("Catch up policy")
+    );
+    ensure!(
+        config.executor_tip.is_none(),
+        unsupported_by_vault("Executor tip")
+    );
+    ensure!(config.croncat.is_none(), unsupported_by_vault("Croncat"));
+    ensure!(
+        config.start_at.is_none() && config.end_at.is_none(),
+        unsupported_by_vault("Strategy start or end")
+    );
+
+    let state = State {
+        status: DcaStatus::Active,
+        next_swap: get_next_swap(&config.schedule, &env.block)?,
+        paused: false,
+        num_trades_executed: Uint128::zero(),
+        swap_status: vec![],
+        total_bought: vec![],
+        carry_over: vec![],
+        average_prices: vec![],
+    };
+
+    let position_id = NEXT_POSITION_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_POSITION_ID.save(deps.storage, &(position_id + 1))?;
+
+    let owner = deps.api.addr_validate(&config.owner)?;
+    let source_balance = get_total_trade_amount(&config)?;
+
+    POSITIONS.save(deps.storage, position_id, &(config, state))?;
+    POSITIONS_BY_OWNER.save(deps.storage, (&owner, position_id), &Empty {})?;
+    SOURCE_BALANCES.save(deps.storage, position_id, &source_balance)?;
+
+    let msgs = get_platform_fee_msg(deps.as_ref(), &position)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "try_create_position")
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("owner", owner))
+}
+
+fn unsupported_by_vault(option: &str) -> ContractError {
+    ContractError::CustomError {
+        val: format!("{option} is not supported by the vault"),
+    }
+}
+
+pub fn try_perform_dca(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut position_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    // a position can only be swapped once per call
+    position_ids.sort_unstable();
+    position_ids.dedup();
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    let mut executed: Vec<String> = vec![];
+
+    for position_id in position_ids {
+        // positions that are unknown or not due are skipped, so one bad id doesn't block the whole batch
+        let (config, state) = match POSITIONS.may_load(deps.storage, position_id)? {
+            Some(position) => position,
+            None => continue,
+        };
+        if !is_executor(&config, &info.sender)
+            || state.status != DcaStatus::Active
+            || state.paused
+            || state.num_trades_executed >= config.num_trades
+            || !state.next_swap.is_expired(&env.block)
+        {
+            continue;
+        }
+
+        let trade_amount = get_trade_amount(&config, state.num_trades_executed)?;
+        let swaps = get_destination_funds(&config, trade_amount);
+        let total_in = swaps
+            .iter()
+            .fold(Uint128::zero(), |acc, funds| acc + funds.amount);
+
+        SOURCE_BALANCES.update(deps.storage, position_id, |balance| {
+            balance
+                .unwrap_or_default()
+                .checked_sub(total_in)
+                .map_err(StdError::from)
+        })?;
+
+        for (destination, in_funds) in config.destinations.iter().zip(swaps) {
+            let msg = WasmMsg::Execute {
+                contract_addr: config.router_contract.to_string(),
+                msg: to_binary(&swaprouter::msg::ExecuteMsg::Swap {
+                    input_coin: in_funds.clone(),
+                    output_denom: destination.denom.clone(),
+                    slippage: swaprouter::msg::Slippage::Twap {
+                        slippage_percentage: config.max_slippage,
+                        window_seconds: Option::Some(config.twap_window_seconds),
+                    },
+                })?,
+                funds: vec![in_funds],
+            };
+
+            // the position id is used as reply id, so every reply can be credited to its position
+            msgs.push(SubMsg::reply_always(msg, position_id));
+        }
+
+        executed.push(position_id.to_string());
+    }
+
+    ensure!(!executed.is_empty(), ContractError::NoDuePositions {});
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("method", "try_perform_dca")
+        .add_attribute("position_ids", executed.join(",")))
+}
+
+pub fn pause_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response, ContractError> {
+    let (config, mut state) = POSITIONS.load(deps.storage, position_id)?;
+    ensure_active(&state)?;
+    ensure!(!state.paused, ContractError::DcaPaused);
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    state.paused = true;
+    POSITIONS.save(deps.storage, position_id, &(config, state))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "pause_position")
+        .add_attribute("position_id", position_id.to_string()))
+}
+
+pub fn resume_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response, ContractError> {
+    let (config, mut state) = POSITIONS.load(deps.storage, position_id)?;
+    ensure_active(&state)?;
+    ensure!(state.paused, ContractError::DcaNotPaused);
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    if state.next_swap.is_expired(&env.block) {
+        state.next_swap = get_next_swap(&config.schedule, &env.block)?;
+    }
+    state.paused = false;
+    let next_swap = state.next_swap;
+    POSITIONS.save(deps.storage, position_id, &(config, state))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "resume_position")
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("next_swap", next_swap.to_string()))
+}
+
+pub fn try_cancel_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response, ContractError> {
+    let (config, _) = POSITIONS.load(deps.storage, position_id)?;
+    ensure_eq!(config.owner, info.sender, ContractError::Unauthorized {});
+
+    let balance = SOURCE_BALANCES.load(deps.storage, position_id)?;
+
+    POSITIONS.remove(deps.storage, position_id);
+    POSITIONS_BY_OWNER.remove(deps.storage, (&info.sender, position_id));
+    SOURCE_BALANCES.remove(deps.storage, position_id);
+
+    // bought tokens are forwarded after every trade, so only the source funds are left
+    let mut msgs = vec![];
+    if !balance.is_zero() {
+        msgs.push(BankMsg::Send {
+            to_address: config.owner,
+            amount: vec![Coin {
+                denom: config.source_denom,
+                amount: balance,
+            }],
+        });
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "try_cancel_position")
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("refunded", balance))
+}
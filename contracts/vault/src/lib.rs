@@ -0,0 +1,7 @@
+pub mod contract;
+pub mod execute;
+pub mod query;
+pub mod state;
+
+#[cfg(test)]
+pub mod multitest;
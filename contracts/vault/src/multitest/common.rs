@@ -0,0 +1,85 @@
+pub use anyhow::Result;
+pub use derivative::Derivative;
+
+pub use crate::contract::{
+    execute as executeVault, instantiate as instantiateVault, query as queryVault,
+    reply as replyVault,
+};
+pub use cosmwasm_std::{
+    coin, to_binary, Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
+    Response, StdError, StdResult, Uint128,
+};
+pub use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+
+pub use cw_utils::Duration;
+
+pub use swaprouter::msg::{
+    ExecuteMsg as RouterExecuteMsg, InstantiateMsg as RouterInstantiateMsg,
+    QueryMsg as RouterQueryMsg, SwapResponse,
+};
+
+pub use phase_finance::{
+    error::ContractError as VaultContractError,
+    msg::InstantiateMsg as DCAInstantiateMsg,
+    types::{
+        CoinWeight, DcaStatus, ExecutionMode, ExecutorPolicy, ExecutorTip, Schedule, StrategyType,
+    },
+    vault::{
+        ExecuteMsg as VaultExecuteMsg, InstantiateMsg as VaultInstantiateMsg, PositionResponse,
+        PositionsResponse, QueryMsg as VaultQueryMsg,
+    },
+};
+
+pub const USER: &str = "user";
+pub const OTHER_USER: &str = "other_user";
+pub const RECIPIENT: &str = "recipient";
+pub const DEPLOYER: &str = "deployer";
+pub const EXECUTOR: &str = "executor";
+pub const DENOM: &str = "uosmo";
+// swaps into this denom always fail in the mock router
+pub const FAIL_DENOM: &str = "ufail";
+
+pub fn contract_vault() -> Box<dyn Contract<Empty>> {
+    let contract =
+        ContractWrapper::new(executeVault, instantiateVault, queryVault).with_reply(replyVault);
+    Box::new(contract)
+}
+
+/// router that swaps 1:1 out of its own balance and answers like the swaprouter does
+pub fn contract_mock_router() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, info: MessageInfo, msg: RouterExecuteMsg| match msg {
+            RouterExecuteMsg::Swap {
+                input_coin,
+                output_denom,
+                ..
+            } => {
+                if output_denom == FAIL_DENOM {
+                    return Err(StdError::generic_err("swap failed"));
+                }
+
+                let response = SwapResponse {
+                    original_sender: info.sender.to_string(),
+                    token_out_denom: output_denom.clone(),
+                    amount: input_coin.amount,
+                };
+
+                Ok(Response::new()
+                    .add_message(BankMsg::Send {
+                        to_address: info.sender.to_string(),
+                        amount: vec![coin(input_coin.amount.u128(), output_denom)],
+                    })
+                    .set_data(to_binary(&response)?)
+                    .add_attribute("token_out_amount", input_coin.amount))
+            }
+            _ => Err(StdError::generic_err("not supported by the mock router")),
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: RouterInstantiateMsg| {
+            Ok::<_, StdError>(Response::new())
+        },
+        |_deps: Deps, _env: Env, _msg: RouterQueryMsg| -> StdResult<Binary> {
+            Err(StdError::generic_err("not supported by the mock router"))
+        },
+    );
+    Box::new(contract)
+}
@@ -0,0 +1,3 @@
+pub mod common;
+pub mod suite;
+pub mod vault;
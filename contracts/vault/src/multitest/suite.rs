@@ -0,0 +1,238 @@
+use crate::multitest::common::*;
+use cw_multi_test::App;
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct VaultSuite {
+    #[derivative(Debug = "ignore")]
+    pub app: App,
+    // The account that deploys everything
+    pub deployer: Addr,
+    // executor address
+    pub executor: Addr,
+    // user address
+    pub user: Addr,
+    // second user sharing the vault
+    pub other_user: Addr,
+    // recipient of the bought tokens
+    pub recipient: Addr,
+    // vault address
+    pub vault: Addr,
+    // router address
+    pub router: Addr,
+}
+
+impl VaultSuite {
+    pub fn init() -> Result<VaultSuite> {
+        let genesis_funds = vec![
+            coin(150000, DENOM),
+            coin(100000, "uion"),
+            coin(100000, "ujuno"),
+        ];
+        let deployer = Addr::unchecked(DEPLOYER);
+        let executor = Addr::unchecked(EXECUTOR);
+        let user = Addr::unchecked(USER);
+        let other_user = Addr::unchecked(OTHER_USER);
+        let recipient = Addr::unchecked(RECIPIENT);
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &deployer, genesis_funds)
+                .unwrap();
+        });
+        app.send_tokens(deployer.clone(), user.clone(), &[coin(50000, DENOM)])?;
+        app.send_tokens(deployer.clone(), other_user.clone(), &[coin(50000, DENOM)])?;
+
+        let vault_id = app.store_code(contract_vault());
+        let router_id = app.store_code(contract_mock_router());
+
+        let router = app
+            .instantiate_contract(
+                router_id,
+                deployer.clone(),
+                &RouterInstantiateMsg {
+                    owner: deployer.to_string(),
+                },
+                &[],
+                "router_contract",
+                Some(deployer.to_string()),
+            )
+            .unwrap();
+        app.send_tokens(
+            deployer.clone(),
+            router.clone(),
+            &[coin(100000, "uion"), coin(100000, "ujuno")],
+        )?;
+
+        let vault = app
+            .instantiate_contract(
+                vault_id,
+                deployer.clone(),
+                &VaultInstantiateMsg {},
+                &[],
+                "vault_contract",
+                Some(deployer.to_string()),
+            )
+            .unwrap();
+
+        Ok(VaultSuite {
+            app,
+            deployer,
+            executor,
+            user,
+            other_user,
+            recipient,
+            vault,
+            router,
+        })
+    }
+
+    pub fn default_position(&self) -> DCAInstantiateMsg {
+        DCAInstantiateMsg {
+            owner: None,
+            factory: None,
+            recipient_address: self.recipient.to_string(),
+            executor_policy: Some(ExecutorPolicy::Single(self.executor.clone())),
+            router_contract: self.router.to_string(),
+            strategy_type: StrategyType::Linear,
+            failure_policy: None,
+            execution_mode: None,
+            catch_up_policy: None,
+            executor_tip: None,
+            max_slippage: Decimal::from_ratio(1u128, 100u128),
+            twap_window_seconds: 1,
+            num_trades: Uint128::from(10u128),
+            schedule: Schedule::Interval(Duration::Time(1)),
+            start_at: None,
+            end_at: None,
+            source_denom: DENOM.to_string(),
+            amount_per_trade: Uint128::from(10u128),
+            destinations: vec![
+                CoinWeight {
+                    denom: "uion".to_string(),
+                    weight: Uint128::from(100u128),
+                    max_price: None,
+                },
+                CoinWeight {
+                    denom: "ujuno".to_string(),
+                    weight: Uint128::from(100u128),
+                    max_price: None,
+                },
+            ],
+            platform_fee: Uint128::zero(),
+            platform_fee_recipient: "osmo123".to_string(),
+            croncat: None,
+        }
+    }
+
+    pub fn create_position(
+        &mut self,
+        sender: &Addr,
+        position: DCAInstantiateMsg,
+        funds: &[Coin],
+    ) -> Result<u64, VaultContractError> {
+        let msg = VaultExecuteMsg::CreatePosition {
+            position: Box::new(position),
+        };
+        let res = self
+            .app
+            .execute_contract(sender.clone(), self.vault.clone(), &msg, funds)
+            .map_err(|err| err.downcast::<VaultContractError>().unwrap())?;
+
+        let position_id = res
+            .events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == "position_id")
+            .map(|attr| attr.value.parse().unwrap())
+            .unwrap();
+        Ok(position_id)
+    }
+
+    pub fn perform_dca(
+        &mut self,
+        sender: &Addr,
+        position_ids: Vec<u64>,
+    ) -> Result<(), VaultContractError> {
+        let msg = VaultExecuteMsg::PerformDca { position_ids };
+        self.app
+            .execute_contract(sender.clone(), self.vault.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    pub fn pause_position(
+        &mut self,
+        sender: &Addr,
+        position_id: u64,
+    ) -> Result<(), VaultContractError> {
+        let msg = VaultExecuteMsg::PausePosition { position_id };
+        self.app
+            .execute_contract(sender.clone(), self.vault.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    pub fn resume_position(
+        &mut self,
+        sender: &Addr,
+        position_id: u64,
+    ) -> Result<(), VaultContractError> {
+        let msg = VaultExecuteMsg::ResumePosition { position_id };
+        self.app
+            .execute_contract(sender.clone(), self.vault.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    pub fn cancel_position(
+        &mut self,
+        sender: &Addr,
+        position_id: u64,
+    ) -> Result<(), VaultContractError> {
+        let msg = VaultExecuteMsg::CancelPosition { position_id };
+        self.app
+            .execute_contract(sender.clone(), self.vault.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    pub fn query_position(&self, position_id: u64) -> StdResult<PositionResponse> {
+        let msg = VaultQueryMsg::Position { position_id };
+        self.app.wrap().query_wasm_smart(self.vault.clone(), &msg)
+    }
+
+    pub fn query_positions_by_owner(
+        &self,
+        owner: &Addr,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<PositionsResponse> {
+        let msg = VaultQueryMsg::PositionsByOwner {
+            owner: owner.to_string(),
+            start_after,
+            limit,
+        };
+        self.app.wrap().query_wasm_smart(self.vault.clone(), &msg)
+    }
+
+    pub fn query_positions(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<PositionsResponse> {
+        let msg = VaultQueryMsg::Positions { start_after, limit };
+        self.app.wrap().query_wasm_smart(self.vault.clone(), &msg)
+    }
+
+    pub fn query_balance(&self, addr: &Addr, denom: &str) -> StdResult<Coin> {
+        self.app.wrap().query_balance(addr.as_str(), denom)
+    }
+
+    pub fn fast_forward_block_time(&mut self, forward_time_sec: u64) {
+        self.app.update_block(|block| {
+            block.height += 10;
+            block.time = block.time.plus_seconds(forward_time_sec);
+        });
+    }
+}
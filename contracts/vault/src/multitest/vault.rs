@@ -0,0 +1,254 @@
+use crate::multitest::common::*;
+use crate::multitest::suite::*;
+
+#[test]
+fn positions_are_tracked_per_owner() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+    let other_user = suite.other_user.clone();
+
+    let first = suite
+        .create_position(&user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+    let second = suite
+        .create_position(&other_user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+    assert_eq!((first, second), (0, 1));
+
+    let position = suite.query_position(first).unwrap();
+    assert_eq!(position.config.owner, user.to_string());
+    assert_eq!(position.source_balance, Uint128::from(100u128));
+
+    let by_owner = suite
+        .query_positions_by_owner(&other_user, None, None)
+        .unwrap();
+    assert_eq!(by_owner.positions.len(), 1);
+    assert_eq!(by_owner.positions[0].position_id, second);
+
+    let all = suite.query_positions(Some(first), None).unwrap();
+    assert_eq!(all.positions.len(), 1);
+    assert_eq!(all.positions[0].position_id, second);
+
+    // the vault holds both deposits
+    assert_eq!(
+        suite.query_balance(&suite.vault.clone(), DENOM).unwrap(),
+        coin(200, DENOM)
+    );
+}
+
+#[test]
+fn unsupported_options_are_rejected_one_by_one() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+
+    let mut position = suite.default_position();
+    position.execution_mode = Some(ExecutionMode::Atomic);
+    let err = suite
+        .create_position(&user, position, &[coin(100, DENOM)])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        VaultContractError::CustomError {
+            val: "Execution mode is not supported by the vault".to_string()
+        }
+    );
+
+    let mut position = suite.default_position();
+    position.executor_tip = Some(ExecutorTip::Fixed {
+        amount: Uint128::one(),
+    });
+    let err = suite
+        .create_position(&user, position, &[coin(110, DENOM)])
+        .unwrap_err();
+    assert_eq!(
+        err,
+        VaultContractError::CustomError {
+            val: "Executor tip is not supported by the vault".to_string()
+        }
+    );
+}
+
+#[test]
+fn perform_dca_executes_every_due_position() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+    let other_user = suite.other_user.clone();
+    let executor = suite.executor.clone();
+
+    let first = suite
+        .create_position(&user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+    let second = suite
+        .create_position(&other_user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+
+    // nothing is due yet
+    let err = suite
+        .perform_dca(&executor, vec![first, second])
+        .unwrap_err();
+    assert_eq!(err, VaultContractError::NoDuePositions {});
+
+    suite.fast_forward_block_time(10);
+    suite.perform_dca(&executor, vec![first, second]).unwrap();
+
+    for position_id in [first, second] {
+        let position = suite.query_position(position_id).unwrap();
+        assert_eq!(position.state.num_trades_executed, Uint128::one());
+        assert!(position.state.swap_status.is_empty());
+        assert_eq!(position.source_balance, Uint128::from(90u128));
+    }
+
+    let recipient = suite.recipient.clone();
+    assert_eq!(
+        suite.query_balance(&recipient, "uion").unwrap(),
+        coin(10, "uion")
+    );
+    assert_eq!(
+        suite.query_balance(&recipient, "ujuno").unwrap(),
+        coin(10, "ujuno")
+    );
+
+    // the positions are not due again until the next interval
+    let err = suite.perform_dca(&executor, vec![first]).unwrap_err();
+    assert_eq!(err, VaultContractError::NoDuePositions {});
+}
+
+#[test]
+fn perform_dca_skips_paused_and_foreign_positions() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+    let executor = suite.executor.clone();
+
+    let paused = suite
+        .create_position(&user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+    let mut position = suite.default_position();
+    position.executor_policy = Some(ExecutorPolicy::Single(Addr::unchecked("other_executor")));
+    let foreign = suite
+        .create_position(&user, position, &[coin(100, DENOM)])
+        .unwrap();
+    let due = suite
+        .create_position(&user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+
+    suite.pause_position(&user, paused).unwrap();
+    suite.fast_forward_block_time(10);
+    suite
+        .perform_dca(&executor, vec![paused, foreign, due])
+        .unwrap();
+
+    assert!(suite
+        .query_position(paused)
+        .unwrap()
+        .state
+        .num_trades_executed
+        .is_zero());
+    assert!(suite
+        .query_position(foreign)
+        .unwrap()
+        .state
+        .num_trades_executed
+        .is_zero());
+    assert_eq!(
+        suite.query_position(due).unwrap().state.num_trades_executed,
+        Uint128::one()
+    );
+
+    suite.resume_position(&user, paused).unwrap();
+    let err = suite.resume_position(&user, paused).unwrap_err();
+    assert_eq!(err, VaultContractError::DcaNotPaused);
+}
+
+#[test]
+fn last_trade_completes_the_position() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+    let executor = suite.executor.clone();
+
+    let mut position = suite.default_position();
+    position.num_trades = Uint128::one();
+    let position_id = suite
+        .create_position(&user, position, &[coin(10, DENOM)])
+        .unwrap();
+
+    // an unknown id doesn't block the due position
+    suite.fast_forward_block_time(10);
+    suite.perform_dca(&executor, vec![42, position_id]).unwrap();
+
+    let position = suite.query_position(position_id).unwrap();
+    assert_eq!(position.state.status, DcaStatus::Completed);
+    assert_eq!(position.state.num_trades_executed, Uint128::one());
+
+    suite.fast_forward_block_time(10);
+    let err = suite.perform_dca(&executor, vec![position_id]).unwrap_err();
+    assert_eq!(err, VaultContractError::NoDuePositions {});
+
+    // a completed position can't be paused, nor rescheduled by a resume
+    let err = suite.pause_position(&user, position_id).unwrap_err();
+    assert_eq!(err, VaultContractError::DcaCompleted);
+    let err = suite.resume_position(&user, position_id).unwrap_err();
+    assert_eq!(err, VaultContractError::DcaCompleted);
+}
+
+#[test]
+fn failed_swap_keeps_input_in_position() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+    let executor = suite.executor.clone();
+
+    let mut position = suite.default_position();
+    position.destinations[1].denom = FAIL_DENOM.to_string();
+    let position_id = suite
+        .create_position(&user, position, &[coin(100, DENOM)])
+        .unwrap();
+
+    suite.fast_forward_block_time(10);
+    suite.perform_dca(&executor, vec![position_id]).unwrap();
+
+    let position = suite.query_position(position_id).unwrap();
+    assert_eq!(position.state.num_trades_executed, Uint128::one());
+    assert_eq!(position.source_balance, Uint128::from(95u128));
+}
+
+#[test]
+fn cancel_position_refunds_only_its_own_funds() {
+    let mut suite = VaultSuite::init().unwrap();
+    let user = suite.user.clone();
+    let other_user = suite.other_user.clone();
+    let executor = suite.executor.clone();
+
+    let first = suite
+        .create_position(&user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+    let second = suite
+        .create_position(&other_user, suite.default_position(), &[coin(100, DENOM)])
+        .unwrap();
+
+    suite.fast_forward_block_time(10);
+    suite.perform_dca(&executor, vec![first]).unwrap();
+
+    let err = suite.cancel_position(&other_user, first).unwrap_err();
+    assert_eq!(err, VaultContractError::Unauthorized {});
+
+    suite.cancel_position(&user, first).unwrap();
+    assert_eq!(
+        suite.query_balance(&user, DENOM).unwrap(),
+        coin(49990, DENOM)
+    );
+    assert!(suite.query_position(first).is_err());
+    assert!(suite
+        .query_positions_by_owner(&user, None, None)
+        .unwrap()
+        .positions
+        .is_empty());
+
+    // the other position is untouched
+    assert_eq!(
+        suite.query_position(second).unwrap().source_balance,
+        Uint128::from(100u128)
+    );
+    assert_eq!(
+        suite.query_balance(&suite.vault.clone(), DENOM).unwrap(),
+        coin(100, DENOM)
+    );
+}
@@ -0,0 +1,64 @@
+use cosmwasm_std::{Deps, Order, StdResult};
+use cw_storage_plus::Bound;
+
+use phase_finance::constants::{DEFAULT_LIMIT, MAX_LIMIT};
+use phase_finance::vault::{PositionResponse, PositionsResponse};
+
+use crate::state::{POSITIONS, POSITIONS_BY_OWNER, SOURCE_BALANCES};
+
+pub fn query_position(deps: Deps, position_id: u64) -> StdResult<PositionResponse> {
+    let (config, state) = POSITIONS.load(deps.storage, position_id)?;
+    let source_balance = SOURCE_BALANCES.load(deps.storage, position_id)?;
+
+    Ok(PositionResponse {
+        position_id,
+        config,
+        state,
+        source_balance,
+    })
+}
+
+pub fn query_positions_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PositionsResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let positions = POSITIONS_BY_OWNER
+        .prefix(&owner)
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|position_id| query_position(deps, position_id?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PositionsResponse { positions })
+}
+
+pub fn query_positions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PositionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let positions = POSITIONS
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|position_id| query_position(deps, position_id?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PositionsResponse { positions })
+}
@@ -0,0 +1,10 @@
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw_storage_plus::{Item, Map};
+use phase_finance::types::{DcaConfig, State};
+
+// id that will be assigned to the next position
+pub const NEXT_POSITION_ID: Item<u64> = Item::new("next_position_id");
+pub const POSITIONS: Map<u64, (DcaConfig, State)> = Map::new("positions");
+pub const POSITIONS_BY_OWNER: Map<(&Addr, u64), Empty> = Map::new("positions_by_owner");
+// source funds of each position that have not been swapped yet
+pub const SOURCE_BALANCES: Map<u64, Uint128> = Map::new("source_balances");
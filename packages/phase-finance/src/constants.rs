@@ -1 +1,11 @@
-pub const DCA_SWAP_ID: u64 = 0x8008135;
+// swap reply ids have one of these bits set, the trade and destination index are packed below them
+pub const DCA_SWAP_REPLY_FLAG: u64 = 1 << 63;
+pub const DCA_RETRY_REPLY_FLAG: u64 = 1 << 62;
+pub const INSTANTIATE_DCA_ID: u64 = 0x8008136;
+pub const CRONCAT_CREATE_TASK_ID: u64 = 0x8008137;
+pub const CRONCAT_REMOVE_TASK_ID: u64 = 0x8008138;
+pub const CRONCAT_REPLACE_TASK_ID: u64 = 0x8008139;
+pub const CRONCAT_REFILL_TASK_ID: u64 = 0x800813a;
+// default and maximum page sizes for paginated queries
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
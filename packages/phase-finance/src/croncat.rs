@@ -0,0 +1,63 @@
+//! the subset of the croncat manager api used to schedule trades, defined here so the strategies
+//! don't depend on a croncat release
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{CosmosMsg, Uint128};
+
+/// how a strategy is scheduled with croncat, set at instantiate
+#[cw_serde]
+pub struct CroncatConfig {
+    /// the croncat manager the task is created with
+    pub manager_address: String,
+    /// what the manager charges for executing a single trade, paid in the source denom
+    pub fee_per_trade: Uint128,
+    /// gas limit of the PerformDca action
+    pub gas_limit: Option<u64>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// create a task owned by the sender, the funds sent along pay for its executions
+    CreateTask { task: TaskRequest },
+    /// remove a task of the sender and refund what is left of its balance
+    RemoveTask { task_hash: String },
+    /// add the funds sent along to the balance of a task
+    RefillTaskBalance { task_hash: String },
+    /// called by agents to execute the next task that is due
+    ProxyCall {},
+}
+
+#[cw_serde]
+pub struct TaskRequest {
+    pub interval: Interval,
+    pub boundary: Option<Boundary>,
+    pub stop_on_fail: bool,
+    pub actions: Vec<Action>,
+}
+
+#[cw_serde]
+pub enum Interval {
+    /// every few blocks
+    Block(u64),
+    /// on a cron schedule with seconds, e.g. "0 */5 * * * *"
+    Cron(String),
+}
+
+/// when the task may run, in block heights or in nanoseconds since the unix epoch
+#[cw_serde]
+pub enum Boundary {
+    Height {
+        start: Option<u64>,
+        end: Option<u64>,
+    },
+    Time {
+        start: Option<u64>,
+        end: Option<u64>,
+    },
+}
+
+#[cw_serde]
+pub struct Action {
+    pub msg: CosmosMsg,
+    pub gas_limit: Option<u64>,
+}
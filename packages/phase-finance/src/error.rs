@@ -1,6 +1,6 @@
 use cosmwasm_std::StdError;
 use cw_denom::DenomError;
-use cw_utils::PaymentError;
+use cw_utils::{Expiration, PaymentError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -20,8 +20,8 @@ pub enum ContractError {
     #[error("No balance")]
     NoBalance {},
 
-    #[error("DCA swap not allowed yet, next swap will be executable at {next_swap_event_time:?}")]
-    DcaSwapNotAllowedYet { next_swap_event_time: u64 },
+    #[error("DCA swap not allowed yet, next swap will be executable at {next_swap}")]
+    DcaSwapNotAllowedYet { next_swap: Expiration },
 
     #[error("DCA strategy is paused")]
     DcaPaused,
@@ -29,9 +29,33 @@ pub enum ContractError {
     #[error("DCA strategy is not paused")]
     DcaNotPaused,
 
+    #[error("DCA strategy is completed")]
+    DcaCompleted,
+
+    #[error("DCA strategy is cancelled")]
+    DcaCancelled,
+
+    #[error("DCA strategy is settled")]
+    DcaSettled,
+
+    #[error("DCA strategy ended at {end_at}")]
+    DcaEnded { end_at: Expiration },
+
+    #[error("DCA strategy has not ended yet")]
+    DcaNotEnded,
+
     #[error("Reached max trade limit")]
     MaxTradeLimit {},
 
+    #[error("None of the given positions can be executed")]
+    NoDuePositions {},
+
+    #[error("Cannot migrate from {previous_contract} {previous_version}")]
+    CannotMigrate {
+        previous_contract: String,
+        previous_version: String,
+    },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
@@ -0,0 +1,92 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Timestamp};
+
+use crate::msg::InstantiateMsg as DcaInstantiateMsg;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// owner of the factory, defaults to the sender
+    pub owner: Option<String>,
+    /// code id of the pf-dca contract that will be instantiated
+    pub dca_code_id: u64,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// instantiate a new pf-dca strategy owned by the sender, funds are forwarded to the strategy
+    CreateDca {
+        instantiate_msg: Box<DcaInstantiateMsg>,
+        label: Option<String>,
+    },
+    /// update the owner of the factory or the pf-dca code id used for new strategies
+    UpdateConfig {
+        owner: Option<String>,
+        dca_code_id: Option<u64>,
+    },
+    /// sent by a strategy of this factory when its recipient changes, keeps the recipient index current
+    UpdateRecipient { recipient: String },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// get the factory config
+    #[returns(FactoryConfig)]
+    Config {},
+    /// get a single strategy created by this factory
+    #[returns(DcaRecord)]
+    Dca { address: String },
+    /// get all strategies owned by an address
+    #[returns(DcasResponse)]
+    DcasByOwner {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// get all strategies sending bought tokens to an address
+    #[returns(DcasResponse)]
+    DcasByRecipient {
+        recipient: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// get all strategies created by this factory
+    #[returns(DcasResponse)]
+    AllDcas {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// get the strategies that still have trades left to execute, at most a fixed number of
+    /// strategies is scanned per page so continue from `next_start_after` until it is none
+    #[returns(ActiveDcasResponse)]
+    ActiveDcas {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct FactoryConfig {
+    pub owner: Addr,
+    pub dca_code_id: u64,
+}
+
+#[cw_serde]
+pub struct DcaRecord {
+    pub address: Addr,
+    pub owner: Addr,
+    pub recipient: Addr,
+    pub created_at: Timestamp,
+}
+
+#[cw_serde]
+pub struct DcasResponse {
+    pub dcas: Vec<DcaRecord>,
+}
+
+#[cw_serde]
+pub struct ActiveDcasResponse {
+    pub dcas: Vec<DcaRecord>,
+    /// last strategy scanned, none once every strategy has been scanned
+    pub next_start_after: Option<Addr>,
+}
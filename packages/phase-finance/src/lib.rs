@@ -1,4 +1,7 @@
 pub mod constants;
+pub mod croncat;
 pub mod error;
+pub mod factory;
 pub mod msg;
 pub mod types;
+pub mod vault;
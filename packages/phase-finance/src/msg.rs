@@ -1,17 +1,41 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Coin, Decimal, Uint128};
-use cw_utils::Duration;
+use cw_utils::Expiration;
 
-use crate::types::{CoinWeight, DcaConfig, State, StrategyType, UpcomingSwapResponse};
+use crate::croncat::CroncatConfig;
+use crate::types::{
+    CatchUpPolicy, CoinWeight, DcaConfig, ExecutionMode, ExecutorPolicy, ExecutorTip,
+    FailurePolicy, Schedule, State, StrategyType, SwapHistoryResponse, UpcomingSwapResponse,
+    UpcomingSwapsResponse,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
+    /// owner of the strategy, defaults to the sender
+    pub owner: Option<String>,
+    /// the factory creating the strategy, filled in by the factory. recipient changes are reported to it
+    pub factory: Option<String>,
     pub recipient_address: String,
-    pub executor_address: Option<String>,
+    /// who performs the trades, defaults to the croncat manager or else the owner alone
+    pub executor_policy: Option<ExecutorPolicy>,
     pub strategy_type: StrategyType,
+    /// what happens to the input of failed swaps, defaults to rolling it over to the next trade
+    pub failure_policy: Option<FailurePolicy>,
+    /// whether the swaps of a trade succeed or fail together, defaults to best effort
+    pub execution_mode: Option<ExecutionMode>,
+    /// what happens to trades that were missed, defaults to skipping them
+    pub catch_up_policy: Option<CatchUpPolicy>,
+    /// paid out of the deposit to whoever performs a trade that buys something, in the source denom.
+    /// the croncat manager is paid by its fee per trade instead
+    pub executor_tip: Option<ExecutorTip>,
     pub amount_per_trade: Uint128,
     pub num_trades: Uint128,
-    pub swap_interval: Duration,
+    pub schedule: Schedule,
+    /// the first trade is due at start_at instead of one interval after instantiation,
+    /// with a cron schedule it's the first time the schedule fires from then on
+    pub start_at: Option<Expiration>,
+    /// no trades are performed past end_at and anyone can settle the strategy
+    pub end_at: Option<Expiration>,
     /// can DCA into multiple coins
     pub destinations: Vec<CoinWeight>,
     // slippage is the same for all swaps, can be changed later
@@ -25,8 +49,15 @@ pub struct InstantiateMsg {
     // platform fee paid in source_denom
     pub platform_fee: Uint128,
     pub platform_fee_recipient: String,
+
+    /// schedule the trades with croncat, the funding of the task is part of the deposit
+    /// and the manager becomes the executor unless a policy is given
+    pub croncat: Option<CroncatConfig>,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub enum ExecuteMsg {
     /// perform swaps required for the dca
@@ -35,6 +66,32 @@ pub enum ExecuteMsg {
     ResumeDca {},
     /// cancel the dca
     CancelDca {},
+    /// return the funds of a strategy past its end, anyone can settle it
+    SettleDca {},
+    /// change the params of a running strategy, only set fields are updated
+    UpdateConfig(UpdateConfigMsg),
+    /// add funds for extra_trades more trades, the funds must match what those trades swap and tip
+    Deposit {
+        extra_trades: Uint128,
+    },
+    /// return the funds of the last `trades` trades to the owner and remove them from the schedule
+    Withdraw {
+        trades: Uint128,
+    },
+    /// swap the input of failed swaps again, only used with the retry queue failure policy
+    RetryFailed {},
+}
+
+#[cw_serde]
+pub struct UpdateConfigMsg {
+    pub max_slippage: Option<Decimal>,
+    pub twap_window_seconds: Option<u64>,
+    pub executor_policy: Option<ExecutorPolicy>,
+    pub recipient_address: Option<String>,
+    pub schedule: Option<Schedule>,
+    pub router_contract: Option<String>,
+    /// new weights of the destinations, in the same order as the destinations
+    pub destination_weights: Option<Vec<Uint128>>,
 }
 
 #[cw_serde]
@@ -43,9 +100,12 @@ pub enum QueryMsg {
     /// get the next swap that will be performed
     #[returns(UpcomingSwapResponse)]
     GetUpcomingSwap {},
-    /// get all upcoming swaps
-    #[returns(Vec<UpcomingSwapResponse>)]
-    GetAllUpcomingSwaps {},
+    /// get every trade left, ordered by trade index
+    #[returns(UpcomingSwapsResponse)]
+    GetAllUpcomingSwaps {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     /// get the amount of funds that are bonded
     #[returns(Coin)]
     GetSourceFunds,
@@ -58,4 +118,12 @@ pub enum QueryMsg {
     /// get the strategy state
     #[returns(State)]
     State {},
+    /// get every swap executed or skipped so far, ordered by trade and destination
+    #[returns(SwapHistoryResponse)]
+    SwapHistory {
+        start_after: Option<(u64, u8)>,
+        limit: Option<u32>,
+        /// only return the swaps into this denom
+        destination: Option<String>,
+    },
 }
@@ -2,15 +2,25 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
 use cw_utils::{Duration, Expiration};
 
+use crate::croncat::CroncatConfig;
+
 #[cw_serde]
 pub struct DcaConfig {
     pub owner: String,
     pub recipient_address: String,
-    pub executor_address: Addr,
+    pub executor_policy: ExecutorPolicy,
     pub strategy_type: StrategyType,
+    pub failure_policy: FailurePolicy,
+    pub execution_mode: ExecutionMode,
+    pub catch_up_policy: CatchUpPolicy,
+    pub executor_tip: Option<ExecutorTip>,
     pub amount_per_trade: Uint128,
     pub num_trades: Uint128,
-    pub swap_interval: Duration,
+    pub schedule: Schedule,
+    // no trades are performed before then, whatever the schedule
+    pub start_at: Option<Expiration>,
+    // no trades are performed from then on and anyone can settle the strategy
+    pub end_at: Option<Expiration>,
     pub source_denom: String,
     // can DCA into multiple coins
     pub destinations: Vec<CoinWeight>,
@@ -19,32 +29,148 @@ pub struct DcaConfig {
     pub twap_window_seconds: u64,
 
     pub router_contract: Addr,
-    // croncat to be added once their contracts are on mainnet
+    // trades are performed by a croncat task when set
+    pub croncat: Option<CroncatConfig>,
+}
+
+#[cw_serde]
+pub enum Schedule {
+    /// a fixed number of blocks or seconds between trades
+    Interval(Duration),
+    /// a cron expression with seconds evaluated in UTC, e.g. "0 0 14 * * MON" for every monday
+    /// at 14:00 or "0 0 0 1 * *" for the first of each month
+    Cron(String),
 }
 
 #[cw_serde]
 pub enum StrategyType {
+    /// every trade swaps amount_per_trade
     Linear,
-    // In theory we can add other DCA Curves here @lrosa
-    // Exponential
+    /// trade n swaps amount_per_trade * factor^n, so a factor above 1 grows the trades
+    /// and a factor below 1 shrinks them
+    Exponential { factor: Decimal },
+    /// trade amounts move in equal steps from start_amount on the first trade to end_amount
+    /// on the last one, amount_per_trade is not used
+    LinearRamp {
+        start_amount: Uint128,
+        end_amount: Uint128,
+    },
+    /// every period the value of the bought tokens (at the router twap) should grow by
+    /// target_increment_per_period, each trade only buys what is missing to reach that target.
+    /// amount_per_trade * num_trades is deposited and caps what can be spent
+    ValueAveraging {
+        target_increment_per_period: Uint128,
+    },
+    /// every destination buys its share of amount_per_trade times the multiplier of the deepest dip
+    /// its twap price is in below the moving average of past execution prices.
+    /// amount_per_trade * num_trades is deposited and caps what can be spent
+    BuyTheDip {
+        multipliers: Vec<DipMultiplier>,
+        // weight of the latest execution price in the exponential moving average, between 0 and 1
+        smoothing: Decimal,
+        // source tokens swapped over the whole schedule at most, below the deposit. none for no cap
+        max_total_spend: Option<Uint128>,
+    },
     // https://seekingalpha.com/article/4151950-hell-highwater-method-vs-dollar-cost-averaging-introduction
     // https://medium.com/fortune-for-future/a-smarter-way-to-dollar-cost-average-the-2-75-50-rule-578895ca49d3
 }
 
+#[cw_serde]
+pub enum FailurePolicy {
+    /// the input of a failed swap is added to the next trade of its destination
+    RollOver,
+    /// failed swaps are queued until the executor retries them with RetryFailed
+    RetryQueue,
+    /// the input of a failed swap is sent back to the owner
+    Refund,
+}
+
+#[cw_serde]
+pub enum ExecutionMode {
+    /// the swaps of a trade succeed together, or the whole trade reverts and is tried again later.
+    /// a destination above its max price rolls the whole trade over to the next one
+    Atomic,
+    /// every swap of a trade stands on its own, failures are handled by the failure policy
+    BestEffort,
+}
+
+#[cw_serde]
+pub enum CatchUpPolicy {
+    /// trades missed while nobody performed them slide forward, the next one is due a schedule
+    /// step after the last trade
+    Skip,
+    /// the trades missed are performed together with the next one, at most max_trades of them in a
+    /// call, and the schedule keeps its original cadence
+    Batch { max_trades: u32 },
+    /// every trade stays due at its original time, missed ones are performed one call at a time
+    Anchor,
+}
+
+#[cw_serde]
+pub enum ExecutorPolicy {
+    /// only this address performs trades
+    Single(Addr),
+    /// any of these addresses performs trades
+    Allowlist(Vec<Addr>),
+    /// anyone performs trades once they are due
+    Anyone,
+}
+
+#[cw_serde]
+pub enum ExecutorTip {
+    /// the same amount of source tokens for every trade
+    Fixed { amount: Uint128 },
+    /// basis points of the amount per trade, only for linear strategies
+    Bps { bps: u16 },
+}
+
+#[cw_serde]
+pub struct DipMultiplier {
+    // how far below the moving average the price has to be, e.g. 0.1 for 10%
+    pub below_average: Decimal,
+    pub multiplier: Decimal,
+}
+
 #[cw_serde]
 pub struct CoinWeight {
     pub denom: String,
     pub weight: Uint128,
+    // highest twap price (in source tokens per destination token) this destination is bought at,
+    // trades above it are skipped and their amount is added to the next trade of this destination
+    pub max_price: Option<Decimal>,
 }
 
 #[cw_serde]
 pub struct UpcomingSwapResponse {
-    pub next_swap: u64,
+    pub trade_index: u64,
+    /// at a height for strategies with a height interval, at a time for those with a time interval
+    pub next_swap: Expiration,
     pub can_execute: bool,
+    /// source tokens swapped into each destination (same order as config.destinations). value
+    /// averaging and buy the dip trades are projected at amount_per_trade, the prices at execution
+    /// decide what they really swap
+    pub amounts: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct UpcomingSwapsResponse {
+    pub swaps: Vec<UpcomingSwapResponse>,
+}
+
+#[cw_serde]
+pub enum DcaStatus {
+    Active,
+    /// every trade was executed, the strategy can only be cancelled to settle what is left
+    Completed,
+    /// the strategy was cancelled and settled, nothing can be executed anymore
+    Cancelled,
+    /// the strategy ended and was settled, nothing can be executed anymore
+    Settled,
 }
 
 #[cw_serde]
 pub struct State {
+    pub status: DcaStatus,
     // epoch time in nanons of the earliest allowed time of the swap that is yet to be executed
     pub next_swap: Expiration,
     // if the strategy is paused
@@ -54,6 +180,13 @@ pub struct State {
 
     // for collecting all swaps in the reply handler and incrementing DCA pending swap
     pub swap_status: Vec<SwapEvent>,
+    // sum of the effective output of all executed swaps, per destination denom
+    pub total_bought: Vec<Coin>,
+    // source tokens skipped because of max_price, per destination (same order as config.destinations)
+    pub carry_over: Vec<Uint128>,
+    // moving average of the execution prices (in source tokens per destination token) of a buy the
+    // dip strategy, per destination. 0 until the destination was bought once
+    pub average_prices: Vec<Decimal>,
 }
 
 #[cw_serde]
@@ -67,3 +200,18 @@ pub struct SwapEvent {
     // the  timestamp for which this swap is scheduled
     pub timestamp_nanos: u64, // here we add other necessary info whenever swaps happen.
 }
+
+#[cw_serde]
+pub struct SwapHistoryEntry {
+    // index of the trade the swap was part of, starting at 0
+    pub trade_index: u64,
+    // index of the destination in config.destinations
+    pub destination_index: u8,
+    pub destination_denom: String,
+    pub swap_event: SwapEvent,
+}
+
+#[cw_serde]
+pub struct SwapHistoryResponse {
+    pub swaps: Vec<SwapHistoryEntry>,
+}
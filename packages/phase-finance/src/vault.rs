@@ -0,0 +1,65 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
+
+use crate::msg::InstantiateMsg as DcaInstantiateMsg;
+use crate::types::{DcaConfig, State};
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// open a new position owned by the sender, funds must match the position params
+    CreatePosition {
+        position: Box<DcaInstantiateMsg>,
+    },
+    /// perform the swaps of every listed position that is due
+    PerformDca {
+        position_ids: Vec<u64>,
+    },
+    PausePosition {
+        position_id: u64,
+    },
+    ResumePosition {
+        position_id: u64,
+    },
+    /// cancel the position and return its unspent funds to the owner
+    CancelPosition {
+        position_id: u64,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// get a single position
+    #[returns(PositionResponse)]
+    Position { position_id: u64 },
+    /// get all positions owned by an address
+    #[returns(PositionsResponse)]
+    PositionsByOwner {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// get all positions in the vault
+    #[returns(PositionsResponse)]
+    Positions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct PositionResponse {
+    pub position_id: u64,
+    pub config: DcaConfig,
+    pub state: State,
+    // source funds of this position that have not been swapped yet
+    pub source_balance: Uint128,
+}
+
+#[cw_serde]
+pub struct PositionsResponse {
+    pub positions: Vec<PositionResponse>,
+}
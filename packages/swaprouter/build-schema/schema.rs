@@ -3,7 +3,9 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use swaprouter::msg::{ExecuteMsg, GetOwnerResponse, GetRouteResponse, InstantiateMsg, QueryMsg};
+use swaprouter::msg::{
+    ExecuteMsg, GetOwnerResponse, GetRouteResponse, GetTwapPriceResponse, InstantiateMsg, QueryMsg,
+};
 use swaprouter::state::{State, SwapMsgReplyState};
 
 fn main() {
@@ -21,4 +23,5 @@ fn main() {
 
     export_schema(&schema_for!(GetOwnerResponse), &out_dir);
     export_schema(&schema_for!(GetRouteResponse), &out_dir);
+    export_schema(&schema_for!(GetTwapPriceResponse), &out_dir);
 }
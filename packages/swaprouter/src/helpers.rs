@@ -100,24 +100,51 @@ pub fn calculate_min_output_from_twap(
     window: Option<u64>,
     percentage_impact: Decimal,
 ) -> Result<Coin, ContractError> {
+    let percentage = percentage_impact.div(Uint128::new(100));
+
+    let mut twap_price = calculate_twap_price(
+        deps,
+        input_token.denom.clone(),
+        output_denom.clone(),
+        now,
+        window,
+    )?;
+
+    twap_price = twap_price - twap_price.mul(percentage);
+    // deps.api.debug(&format!(
+    //     "twap_price minus {percentage_impact}%: {twap_price}"
+    // ));
+
+    let min_out: Uint128 = input_token.amount.mul(twap_price);
+    // deps.api.debug(&format!("min: {min_out}"));
+
+    Ok(Coin::new(min_out.into(), output_denom))
+}
+
+/// price of <out> quoted in <in> along the stored route, i.e. how many output tokens one input token buys
+pub fn calculate_twap_price(
+    deps: Deps,
+    input_denom: String,
+    output_denom: String,
+    now: Timestamp,
+    window: Option<u64>,
+) -> Result<Decimal, ContractError> {
     // get trade route
     let route = ROUTING_TABLE
-        .load(deps.storage, (&input_token.denom, &output_denom))
+        .load(deps.storage, (&input_denom, &output_denom))
         .unwrap_or_default();
     if route.is_empty() {
         return Err(ContractError::InvalidPoolRoute {
-            reason: format!("No route foung for {} -> {output_denom}", input_token.denom),
+            reason: format!("No route foung for {input_denom} -> {output_denom}"),
         });
     }
 
-    let percentage = percentage_impact.div(Uint128::new(100));
-
     let mut twap_price: Decimal = Decimal::one();
 
     // When swapping from input to output, we need to quote the price in the input token
     // For example when seling osmo to buy atom:
     //  price of <out> is X<in> (i.e.: price of atom is Xosmo)
-    let mut sell_denom = input_token.denom;
+    let mut sell_denom = input_denom;
 
     // if duration is not provided, default to 1h
     let start_time = now.minus_seconds(window.unwrap_or(3600));
@@ -170,13 +197,5 @@ pub fn calculate_min_output_from_twap(
         deps.api.debug(&format!("twap_price: {twap_price}"));
     }
 
-    twap_price = twap_price - twap_price.mul(percentage);
-    // deps.api.debug(&format!(
-    //     "twap_price minus {percentage_impact}%: {twap_price}"
-    // ));
-
-    let min_out: Uint128 = input_token.amount.mul(twap_price);
-    // deps.api.debug(&format!("min: {min_out}"));
-
-    Ok(Coin::new(min_out.into(), output_denom))
+    Ok(twap_price)
 }
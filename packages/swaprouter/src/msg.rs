@@ -43,6 +43,12 @@ pub enum QueryMsg {
         input_denom: String,
         output_denom: String,
     },
+    #[returns(GetTwapPriceResponse)]
+    GetTwapPrice {
+        input_denom: String,
+        output_denom: String,
+        window_seconds: Option<u64>,
+    },
 }
 
 // Response for GetOwner query
@@ -57,6 +63,13 @@ pub struct GetRouteResponse {
     pub pool_route: Vec<SwapAmountInRoute>,
 }
 
+// Response for GetTwapPrice query
+#[cw_serde]
+pub struct GetTwapPriceResponse {
+    // amount of output_denom one input_denom buys
+    pub price: Decimal,
+}
+
 // Response for Swap
 #[cw_serde]
 pub struct SwapResponse {
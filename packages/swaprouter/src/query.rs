@@ -1,6 +1,7 @@
-use cosmwasm_std::{Deps, StdResult};
+use cosmwasm_std::{Deps, Env, StdError, StdResult};
 
-use crate::msg::{GetOwnerResponse, GetRouteResponse};
+use crate::helpers::calculate_twap_price;
+use crate::msg::{GetOwnerResponse, GetRouteResponse, GetTwapPriceResponse};
 use crate::state::{ROUTING_TABLE, STATE};
 
 pub fn query_owner(deps: Deps) -> StdResult<GetOwnerResponse> {
@@ -20,3 +21,22 @@ pub fn query_route(
 
     Ok(GetRouteResponse { pool_route: route })
 }
+
+pub fn query_twap_price(
+    deps: Deps,
+    env: Env,
+    input_denom: String,
+    output_denom: String,
+    window_seconds: Option<u64>,
+) -> StdResult<GetTwapPriceResponse> {
+    let price = calculate_twap_price(
+        deps,
+        input_denom,
+        output_denom,
+        env.block.time,
+        window_seconds,
+    )
+    .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(GetTwapPriceResponse { price })
+}